@@ -2,7 +2,7 @@
 set -e
 
 if [ -n "$INPUT_PATH" ]; then
-  exec excalidocker --input-path "$INPUT_PATH" ${SKIP_DEPS:+ --skip-dependencies} ${SKIP_NETWORK:+ --skip-network} --config-path ${CONFIG_PATH:-/excalidocker/bin/excalidocker-config.yaml}
+  exec excalidocker --input-path "$INPUT_PATH" ${SKIP_DEPS:+ --skip-dependencies} ${SKIP_NETWORK:+ --skip-network} ${ENV_FILE:+ --env-file "$ENV_FILE"} --config-path ${CONFIG_PATH:-/excalidocker/bin/excalidocker-config.yaml}
 elif [ -n "$SHOW_CONFIG" ]; then
   exec excalidocker -C --config-path /excalidocker/bin/excalidocker-config.yaml
 else
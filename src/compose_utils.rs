@@ -0,0 +1,590 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::error::ExcalidockerError::{
+    self, CircularReference, FileNotFound, RemoteFileFailedRead, UnresolvedReference,
+};
+use crate::file_utils;
+
+/// Service attributes which are replaced (not appended) when merging
+const REPLACED_SEQUENCES: [&str; 2] = ["command", "entrypoint"];
+/// Service attributes which could be declared either as a `KEY=VALUE` list or as a mapping
+const KEY_VALUE_SEQUENCES: [&str; 5] = [
+    "environment",
+    "labels",
+    "annotations",
+    "extra_hosts",
+    "sysctls",
+];
+/// Service attributes which could be declared either as a list of names or as a mapping
+const NAMED_SEQUENCES: [&str; 2] = ["depends_on", "networks"];
+/// Service attributes where items are unique by their target
+const UNIQUE_BY_TARGET_SEQUENCES: [&str; 4] = ["volumes", "devices", "secrets", "configs"];
+
+/// Merges docker-compose files the same way `docker compose -f a.yml -f b.yml` does.
+/// Files are applied in order, each subsequent file overrides the previous ones:
+///  - mappings are merged recursively
+///  - `command`, `entrypoint` and `healthcheck.test` are replaced
+///  - `environment`, `labels`, etc. are merged as mappings (the `KEY=VALUE` list form is converted)
+///  - `volumes`, `devices`, `secrets` and `configs` are merged by the target
+///  - other sequences are appended
+///  - values tagged with `!reset` are removed, values tagged with `!override` replace the previous ones
+pub fn merge_compose_files(files: Vec<Mapping>) -> Mapping {
+    let mut merged = Mapping::new();
+    for file in files {
+        merge_mapping(&mut merged, file, &mut vec![]);
+    }
+    merged
+}
+
+/// Merges `overriding` service definition into the `base` one
+pub fn merge_service(base: Mapping, overriding: Mapping, service_name: &str) -> Mapping {
+    let mut merged = strip_tags_mapping(base);
+    let mut path = vec!["services".to_string(), service_name.to_string()];
+    merge_mapping(&mut merged, overriding, &mut path);
+    merged
+}
+
+/// Resolves top-level `include` and service level `extends`.
+/// Paths are resolved relative to the file where `include`/`extends` is declared.
+pub fn resolve_include_and_extends(
+    compose: Mapping,
+    file_path: &str,
+    variables: &HashMap<String, String>,
+) -> Result<Mapping, ExcalidockerError> {
+    resolve_references(
+        compose,
+        file_path,
+        variables,
+        &mut vec![reference_key(file_path)],
+    )
+}
+
+/// Included files are loaded (with their own `include`/`extends` resolved)
+/// and the including file is merged on top of them.
+fn resolve_references(
+    mut compose: Mapping,
+    file_path: &str,
+    variables: &HashMap<String, String>,
+    include_chain: &mut Vec<String>,
+) -> Result<Mapping, ExcalidockerError> {
+    let include = compose.remove("include");
+    let compose = resolve_extends(compose, file_path, variables)?;
+    let include = match include {
+        Some(include) => include,
+        None => return Ok(compose),
+    };
+    let mut files = vec![];
+    for included_path in parse_include(&include) {
+        let path = file_utils::resolve_relative_path(file_path, &included_path);
+        let key = reference_key(&path);
+        if include_chain.contains(&key) {
+            return Err(CircularReference {
+                path: file_path.to_string(),
+                msg: format!("include chain {} -> {}", include_chain.join(" -> "), key),
+            });
+        }
+        let Some(included) = read_referenced_file(&path, variables)? else {
+            continue;
+        };
+        include_chain.push(key);
+        files.push(resolve_references(
+            included,
+            &path,
+            variables,
+            include_chain,
+        )?);
+        include_chain.pop();
+    }
+    files.push(compose);
+    Ok(merge_compose_files(files))
+}
+
+/// `include` items could be either a path or a mapping with a `path` (string or list)
+fn parse_include(include: &Value) -> Vec<String> {
+    let as_paths = |value: &Value| match value {
+        Value::String(path) => vec![path.clone()],
+        Value::Sequence(paths) => paths
+            .iter()
+            .filter_map(|path| path.as_str().map(|p| p.to_string()))
+            .collect(),
+        _ => vec![],
+    };
+    match include {
+        Value::Sequence(items) => items
+            .iter()
+            .flat_map(|item| match item {
+                Value::Mapping(item) => item.get("path").map(as_paths).unwrap_or_default(),
+                item => as_paths(item),
+            })
+            .collect(),
+        include => as_paths(include),
+    }
+}
+
+fn resolve_extends(
+    mut compose: Mapping,
+    file_path: &str,
+    variables: &HashMap<String, String>,
+) -> Result<Mapping, ExcalidockerError> {
+    let services = match compose.get("services").and_then(|s| s.as_mapping()) {
+        Some(services) => services.clone(),
+        None => return Ok(compose),
+    };
+    let mut resolved_services = Mapping::new();
+    for service_name in services.keys() {
+        let name = service_name.as_str().unwrap_or_default();
+        let service = resolve_service_extends(&services, name, file_path, variables, &mut vec![])?;
+        resolved_services.insert(service_name.clone(), Value::Mapping(service));
+    }
+    compose.insert(Value::from("services"), Value::Mapping(resolved_services));
+    Ok(compose)
+}
+
+/// `extends` could be declared as a service name (within the same file)
+/// or as a mapping with the `service` and optional `file` attributes
+fn resolve_service_extends(
+    services: &Mapping,
+    service_name: &str,
+    file_path: &str,
+    variables: &HashMap<String, String>,
+    extends_chain: &mut Vec<String>,
+) -> Result<Mapping, ExcalidockerError> {
+    let key = format!("{}#{}", reference_key(file_path), service_name);
+    if extends_chain.contains(&key) {
+        return Err(CircularReference {
+            path: file_path.to_string(),
+            msg: format!("extends chain {} -> {}", extends_chain.join(" -> "), key),
+        });
+    }
+    let mut service = services
+        .get(service_name)
+        .and_then(|service| service.as_mapping())
+        .cloned()
+        .ok_or_else(|| UnresolvedReference {
+            path: file_path.to_string(),
+            msg: format!("service '{}' is not found", service_name),
+        })?;
+    let (base_file, base_service_name) = match service.remove("extends") {
+        None => return Ok(service),
+        Some(Value::String(base_service_name)) => (None, base_service_name),
+        Some(Value::Mapping(extends)) => match extends.get("service").and_then(|s| s.as_str()) {
+            Some(base_service_name) => (
+                extends
+                    .get("file")
+                    .and_then(|f| f.as_str())
+                    .map(|f| f.to_string()),
+                base_service_name.to_string(),
+            ),
+            None => {
+                return Err(UnresolvedReference {
+                    path: file_path.to_string(),
+                    msg: format!(
+                        "'extends' of the service '{}' has no 'service'",
+                        service_name
+                    ),
+                })
+            }
+        },
+        Some(_) => {
+            return Err(UnresolvedReference {
+                path: file_path.to_string(),
+                msg: format!("'extends' of the service '{}' is invalid", service_name),
+            })
+        }
+    };
+    extends_chain.push(key);
+    let base_service = match base_file {
+        None => resolve_service_extends(
+            services,
+            &base_service_name,
+            file_path,
+            variables,
+            extends_chain,
+        )?,
+        Some(base_file) => {
+            let base_file_path = file_utils::resolve_relative_path(file_path, &base_file);
+            let Some(base_compose) = read_referenced_file(&base_file_path, variables)? else {
+                extends_chain.pop();
+                return Ok(service);
+            };
+            let base_services = base_compose
+                .get("services")
+                .and_then(|s| s.as_mapping())
+                .cloned()
+                .unwrap_or_default();
+            resolve_service_extends(
+                &base_services,
+                &base_service_name,
+                &base_file_path,
+                variables,
+                extends_chain,
+            )?
+        }
+    };
+    extends_chain.pop();
+    Ok(merge_service(base_service, service, service_name))
+}
+
+/// Referenced file which is missing (f.e. not downloaded or not checked out) is skipped with a warning
+/// like the services defined in it, the rest of the docker-compose file is still rendered
+fn read_referenced_file(
+    file_path: &str,
+    variables: &HashMap<String, String>,
+) -> Result<Option<Mapping>, ExcalidockerError> {
+    match file_utils::read_docker_compose(file_path, variables) {
+        Ok(compose) => Ok(Some(compose)),
+        Err(err @ (FileNotFound { .. } | RemoteFileFailedRead { .. })) => {
+            eprintln!("{}. The reference is skipped", err);
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Key used to detect circular references, local paths are canonicalized when possible
+fn reference_key(file_path: &str) -> String {
+    fs::canonicalize(file_path)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file_path.to_string())
+}
+
+fn merge_mapping(base: &mut Mapping, overriding: Mapping, path: &mut Vec<String>) {
+    for (key, value) in overriding {
+        if is_tagged(&value, "reset") {
+            base.remove(&key);
+            continue;
+        }
+        path.push(key.as_str().unwrap_or_default().to_string());
+        match base.get_mut(&key) {
+            // the value is updated in place to keep the order of the keys (f.e. services)
+            Some(base_value) => *base_value = merge_value(std::mem::take(base_value), value, path),
+            None => {
+                base.insert(key, strip_tags(value));
+            }
+        }
+        path.pop();
+    }
+}
+
+fn merge_value(base: Value, overriding: Value, path: &mut Vec<String>) -> Value {
+    if is_tagged(&overriding, "override") {
+        return strip_tags(overriding);
+    }
+    let attribute = service_attribute(path);
+    match (base, overriding) {
+        (Value::Mapping(mut base), Value::Mapping(overriding)) => {
+            merge_mapping(&mut base, overriding, path);
+            Value::Mapping(base)
+        }
+        (base, overriding)
+            if attribute.is_some_and(|a| KEY_VALUE_SEQUENCES.contains(&a))
+                || is_build_args(path) =>
+        {
+            let mut base = key_value_to_mapping(base);
+            merge_mapping(&mut base, key_value_to_mapping(overriding), path);
+            Value::Mapping(base)
+        }
+        (base, overriding)
+            if attribute.is_some_and(|a| NAMED_SEQUENCES.contains(&a))
+                && (base.is_mapping() || overriding.is_mapping()) =>
+        {
+            let mut base = names_to_mapping(base);
+            merge_mapping(&mut base, names_to_mapping(overriding), path);
+            Value::Mapping(base)
+        }
+        (Value::Sequence(base), Value::Sequence(overriding)) => {
+            let overriding = strip_tags_sequence(overriding);
+            if attribute.is_some_and(|a| REPLACED_SEQUENCES.contains(&a))
+                || is_healthcheck_test(path)
+            {
+                Value::Sequence(overriding)
+            } else if attribute.is_some_and(|a| UNIQUE_BY_TARGET_SEQUENCES.contains(&a)) {
+                Value::Sequence(merge_unique_by_target(base, overriding))
+            } else {
+                Value::Sequence(append_unique(base, overriding))
+            }
+        }
+        (_, overriding) => strip_tags(overriding),
+    }
+}
+
+/// Returns the service attribute name if the `path` points to `services.<name>.<attribute>`
+fn service_attribute(path: &[String]) -> Option<&str> {
+    match path {
+        [services, _, attribute] if services == "services" => Some(attribute.as_str()),
+        _ => None,
+    }
+}
+
+fn is_healthcheck_test(path: &[String]) -> bool {
+    matches!(path, [services, _, healthcheck, test]
+        if services == "services" && healthcheck == "healthcheck" && test == "test")
+}
+
+fn is_build_args(path: &[String]) -> bool {
+    matches!(path, [services, _, build, args]
+        if services == "services" && build == "build" && args == "args")
+}
+
+fn is_tagged(value: &Value, tag: &str) -> bool {
+    matches!(value, Value::Tagged(tagged) if tagged.tag == tag)
+}
+
+/// Removes `!reset` values and unwraps `!override` ones, it's relevant for the very first file
+/// or for the values which are not present in the file being overridden
+fn strip_tags(value: Value) -> Value {
+    match value {
+        Value::Tagged(tagged) if tagged.tag == "reset" || tagged.tag == "override" => {
+            strip_tags(tagged.value)
+        }
+        Value::Mapping(mapping) => Value::Mapping(strip_tags_mapping(mapping)),
+        Value::Sequence(sequence) => Value::Sequence(strip_tags_sequence(sequence)),
+        value => value,
+    }
+}
+
+fn strip_tags_sequence(sequence: Vec<Value>) -> Vec<Value> {
+    sequence
+        .into_iter()
+        .filter(|item| !is_tagged(item, "reset"))
+        .map(strip_tags)
+        .collect()
+}
+
+fn strip_tags_mapping(mapping: Mapping) -> Mapping {
+    mapping
+        .into_iter()
+        .filter(|(_, value)| !is_tagged(value, "reset"))
+        .map(|(key, value)| (key, strip_tags(value)))
+        .collect()
+}
+
+/// Converts `- KEY=VALUE` list into `KEY: VALUE` mapping
+fn key_value_to_mapping(value: Value) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping,
+        Value::Sequence(sequence) => sequence
+            .into_iter()
+            .filter_map(|item| item.as_str().map(|s| s.to_string()))
+            .map(|item| match item.split_once('=') {
+                Some((key, value)) => (Value::from(key), Value::from(value)),
+                None => (Value::from(item), Value::Null),
+            })
+            .collect(),
+        _ => Mapping::new(),
+    }
+}
+
+/// Converts `- name` list into `name: null` mapping
+fn names_to_mapping(value: Value) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping,
+        Value::Sequence(sequence) => sequence
+            .into_iter()
+            .map(|item| (item, Value::Null))
+            .collect(),
+        _ => Mapping::new(),
+    }
+}
+
+fn append_unique(mut base: Vec<Value>, overriding: Vec<Value>) -> Vec<Value> {
+    for item in overriding {
+        if !base.contains(&item) {
+            base.push(item);
+        }
+    }
+    base
+}
+
+fn merge_unique_by_target(mut base: Vec<Value>, overriding: Vec<Value>) -> Vec<Value> {
+    for item in overriding {
+        let target = sequence_item_target(&item);
+        match base
+            .iter()
+            .position(|base_item| target.is_some() && sequence_item_target(base_item) == target)
+        {
+            Some(index) => base[index] = item,
+            None => base.push(item),
+        }
+    }
+    base
+}
+
+/// Target of the `volumes`/`devices` (container path) and `secrets`/`configs` (name or target) items
+fn sequence_item_target(item: &Value) -> Option<String> {
+    match item {
+        Value::String(short_syntax) => {
+            let parts: Vec<&str> = short_syntax.split(':').collect();
+            match parts.as_slice() {
+                [target] => Some(target.to_string()),
+                [_, target, ..] => Some(target.to_string()),
+                [] => None,
+            }
+        }
+        Value::Mapping(long_syntax) => long_syntax
+            .get("target")
+            .or_else(|| long_syntax.get("source"))
+            .and_then(|target| target.as_str())
+            .map(|target| target.to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_merge_compose_files() {
+    let base: Mapping = serde_yaml::from_str(
+        r#"
+services:
+  web:
+    image: web:1.0
+    command: ["npm", "start"]
+    ports: ["8080:80"]
+    environment:
+      - DEBUG=false
+      - LOG_LEVEL=info
+    volumes:
+      - data:/var/lib/data
+      - ./config:/etc/config:ro
+    depends_on: [db]
+    networks: [frontend]
+  db:
+    image: postgres
+    ports: ["5432:5432"]
+    labels:
+      tier: db
+"#,
+    )
+    .unwrap();
+    let overriding: Mapping = serde_yaml::from_str(
+        r#"
+services:
+  web:
+    image: web:2.0
+    command: ["npm", "run", "dev"]
+    ports: ["9229:9229", "8080:80"]
+    environment:
+      DEBUG: "true"
+    volumes:
+      - ./config-dev:/etc/config
+    depends_on:
+      cache:
+        condition: service_started
+    networks: !override [backend]
+  db:
+    ports: !reset []
+    labels: !override
+      env: dev
+  cache:
+    image: redis
+"#,
+    )
+    .unwrap();
+    let merged = merge_compose_files(vec![base, overriding]);
+    let expected: Mapping = serde_yaml::from_str(
+        r#"
+services:
+  web:
+    image: web:2.0
+    command: ["npm", "run", "dev"]
+    ports: ["8080:80", "9229:9229"]
+    environment:
+      DEBUG: "true"
+      LOG_LEVEL: info
+    volumes:
+      - data:/var/lib/data
+      - ./config-dev:/etc/config
+    depends_on:
+      db: null
+      cache:
+        condition: service_started
+    networks: [backend]
+  db:
+    image: postgres
+    labels:
+      env: dev
+  cache:
+    image: redis
+"#,
+    )
+    .unwrap();
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn test_resolve_include_and_extends() {
+    let file_path = "./data/compose/docker-compose-extends-include.yaml";
+    let variables = HashMap::new();
+    let compose = file_utils::read_docker_compose(file_path, &variables).unwrap();
+    let resolved = resolve_include_and_extends(compose, file_path, &variables).unwrap();
+    let expected: Mapping = serde_yaml::from_str(
+        r#"
+services:
+  prometheus:
+    image: prom/prometheus
+    ports: ["9090:9090"]
+  web:
+    image: node:18
+    command: ["npm", "start"]
+    environment:
+      LOG_LEVEL: info
+      PORT: "80"
+    ports: ["8080:80"]
+  worker:
+    image: node:18
+    command: ["npm", "run", "worker"]
+    environment:
+      LOG_LEVEL: info
+      PORT: "80"
+"#,
+    )
+    .unwrap();
+    assert_eq!(resolved, expected);
+    let service_names: Vec<&str> = resolved["services"]
+        .as_mapping()
+        .unwrap()
+        .keys()
+        .filter_map(|name| name.as_str())
+        .collect();
+    assert_eq!(service_names, vec!["prometheus", "web", "worker"]);
+}
+
+#[test]
+fn test_resolve_extends_errors() {
+    let variables = HashMap::new();
+    let compose: Mapping = serde_yaml::from_str(
+        "services: {a: {extends: b}, b: {extends: {service: a}}, c: {image: busybox}}",
+    )
+    .unwrap();
+    match resolve_include_and_extends(compose, "docker-compose.yaml", &variables) {
+        Err(CircularReference { path, msg }) => {
+            assert_eq!(path, "docker-compose.yaml");
+            assert_eq!(
+                msg,
+                "extends chain docker-compose.yaml#a -> docker-compose.yaml#b -> docker-compose.yaml#a"
+            );
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let compose: Mapping = serde_yaml::from_str("services: {a: {extends: missing}}").unwrap();
+    match resolve_include_and_extends(compose, "docker-compose.yaml", &variables) {
+        Err(UnresolvedReference { msg, .. }) => {
+            assert_eq!(msg, "service 'missing' is not found")
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // missing files are skipped
+    let compose: Mapping = serde_yaml::from_str(
+        "include: [./missing.yaml]\nservices: {a: {image: busybox, extends: {file: ./missing.yaml, service: b}}}",
+    )
+    .unwrap();
+    let expected: Mapping = serde_yaml::from_str("services: {a: {image: busybox}}").unwrap();
+    assert_eq!(
+        resolve_include_and_extends(compose, "docker-compose.yaml", &variables).unwrap(),
+        expected
+    );
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde_yaml::Value;
+
+use crate::error::ExcalidockerError::{self, RequiredVariableMissing};
+use crate::scalar_to_string;
+
+pub const REDACTED_VALUE: &str = "********";
+
+/// Parses the content of an env file (`env_file` / `.env`).
+/// Each line is in `KEY=VALUE` format, the following rules apply:
+///  - blank lines and lines starting with `#` are ignored
+///  - an optional `export ` prefix is ignored
+///  - values could be single or double quoted, for unquoted values ` #` starts an inline comment
+///  - a line with a `KEY` only (no `=`) declares a variable without value
+pub fn parse_env_file_content(content: &str) -> IndexMap<String, Option<String>> {
+    let mut env = IndexMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        match line.split_once('=') {
+            Some((key, value)) => {
+                env.insert(
+                    key.trim().to_string(),
+                    Some(unquote_env_value(value.trim())),
+                );
+            }
+            None => {
+                env.insert(line.to_string(), None);
+            }
+        }
+    }
+    env
+}
+
+fn unquote_env_value(value: &str) -> String {
+    let quoted_with =
+        |quote: char| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if quoted_with('\'') {
+        value[1..value.len() - 1].to_string()
+    } else if quoted_with('"') {
+        value[1..value.len() - 1]
+            .replace("\\n", "\n")
+            .replace("\\\"", "\"")
+    } else {
+        match value.find(" #") {
+            Some(comment_index) => value[..comment_index].trim_end().to_string(),
+            None => value.to_string(),
+        }
+    }
+}
+
+/// Parses service `environment` attribute. Both forms are supported:
+///  - mapping `KEY: value`, where value could be any scalar or `null`
+///  - list `- KEY=value` or `- KEY`
+pub fn parse_environment(value: &Value) -> Option<IndexMap<String, Option<String>>> {
+    match value {
+        Value::Mapping(environment) => Some(
+            environment
+                .iter()
+                .filter_map(|(key, value)| {
+                    key.as_str()
+                        .map(|key| (key.to_string(), scalar_to_string(value)))
+                })
+                .collect(),
+        ),
+        Value::Sequence(environment) => Some(
+            environment
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| match item.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (item.to_string(), None),
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the `key` matches any of the `patterns`.
+/// Patterns are case-insensitive and support `*` wildcard, f.e. `*PASSWORD*`
+pub fn is_redacted(key: &str, patterns: &[String]) -> bool {
+    let key = key.to_uppercase();
+    patterns
+        .iter()
+        .any(|pattern| wildcard_match(&pattern.to_uppercase(), &key))
+}
+
+/// Returns the value to be displayed for the given environment variable
+pub fn redact(key: &str, value: &str, patterns: &[String]) -> String {
+    if is_redacted(key, patterns) {
+        REDACTED_VALUE.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut remaining = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Applies the docker-compose variable interpolation to every string value of the yaml document.
+pub fn interpolate_value(
+    value: &mut Value,
+    variables: &HashMap<String, String>,
+) -> Result<(), ExcalidockerError> {
+    match value {
+        Value::String(s) => *s = interpolate(s, variables)?,
+        Value::Sequence(sequence) => {
+            for item in sequence {
+                interpolate_value(item, variables)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                interpolate_value(item, variables)?;
+            }
+        }
+        Value::Tagged(tagged) => interpolate_value(&mut tagged.value, variables)?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Substitutes variables in the `input` according to the docker-compose specification:
+///  - `$VAR` / `${VAR}` - value of `VAR`, empty string if `VAR` is unset
+///  - `${VAR:-default}` / `${VAR-default}` - `default` if `VAR` is unset or empty / unset
+///  - `${VAR:?err}` / `${VAR?err}` - error if `VAR` is unset or empty / unset
+///  - `${VAR:+replacement}` / `${VAR+replacement}` - `replacement` if `VAR` is set and non-empty / set
+///  - `$$` - literal `$`
+pub fn interpolate(
+    input: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, ExcalidockerError> {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(dollar_index) = rest.find('$') {
+        result.push_str(&rest[..dollar_index]);
+        let after_dollar = &rest[dollar_index + 1..];
+        if let Some(after_escape) = after_dollar.strip_prefix('$') {
+            result.push('$');
+            rest = after_escape;
+        } else if let Some(braced) = after_dollar.strip_prefix('{') {
+            match find_closing_brace(braced) {
+                Some(closing_index) => {
+                    result.push_str(&resolve_braced(&braced[..closing_index], variables)?);
+                    rest = &braced[closing_index + 1..];
+                }
+                None => {
+                    result.push('$');
+                    rest = after_dollar;
+                }
+            }
+        } else {
+            let name_len = variable_name_len(after_dollar);
+            if name_len == 0 {
+                result.push('$');
+            } else if let Some(value) = variables.get(&after_dollar[..name_len]) {
+                result.push_str(value);
+            }
+            rest = &after_dollar[name_len..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn variable_name_len(input: &str) -> usize {
+    if input.starts_with(|c: char| c.is_ascii_digit()) {
+        return 0;
+    }
+    input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(input.len())
+}
+
+/// Returns the index of the `}` closing the expression, nested `${...}` are taken into account
+fn find_closing_brace(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in input.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+fn resolve_braced(
+    expression: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, ExcalidockerError> {
+    let name_len = variable_name_len(expression);
+    let (name, modifier) = expression.split_at(name_len);
+    let value = variables.get(name);
+    let is_set_and_non_empty = value.is_some_and(|v| !v.is_empty());
+    let value_or_empty = || value.cloned().unwrap_or_default();
+    if modifier.is_empty() {
+        return Ok(value_or_empty());
+    }
+    // the operator is one or two characters long, the modifier could hold any unicode characters
+    let operator_chars = if modifier.starts_with(':') { 2 } else { 1 };
+    let operator_len = modifier
+        .char_indices()
+        .nth(operator_chars)
+        .map_or(modifier.len(), |(index, _)| index);
+    let (operator, argument) = modifier.split_at(operator_len);
+    match operator {
+        ":-" if !is_set_and_non_empty => interpolate(argument, variables),
+        "-" if value.is_none() => interpolate(argument, variables),
+        ":?" if !is_set_and_non_empty => Err(required_variable_missing(name, argument, variables)),
+        "?" if value.is_none() => Err(required_variable_missing(name, argument, variables)),
+        ":+" if is_set_and_non_empty => interpolate(argument, variables),
+        "+" if value.is_some() => interpolate(argument, variables),
+        ":+" | "+" => Ok(String::new()),
+        ":-" | "-" | ":?" | "?" => Ok(value_or_empty()),
+        // not a valid modifier, keep the expression as is
+        _ => Ok(format!("${{{}}}", expression)),
+    }
+}
+
+fn required_variable_missing(
+    name: &str,
+    message: &str,
+    variables: &HashMap<String, String>,
+) -> ExcalidockerError {
+    RequiredVariableMissing {
+        name: name.to_string(),
+        msg: interpolate(message, variables).unwrap_or_else(|_| message.to_string()),
+    }
+}
+
+#[test]
+fn test_parse_env_file_content() {
+    let env = parse_env_file_content(
+        r#"
+# comment
+DB_HOST=db
+export DB_PORT=5432
+DB_USER = 'admin'
+DB_PASSWORD="p@ss # word"
+TAG=latest # inline comment
+EMPTY=
+UNSET
+"#,
+    );
+    assert_eq!(env.get("DB_HOST"), Some(&Some("db".to_string())));
+    assert_eq!(env.get("DB_PORT"), Some(&Some("5432".to_string())));
+    assert_eq!(env.get("DB_USER"), Some(&Some("admin".to_string())));
+    assert_eq!(
+        env.get("DB_PASSWORD"),
+        Some(&Some("p@ss # word".to_string()))
+    );
+    assert_eq!(env.get("TAG"), Some(&Some("latest".to_string())));
+    assert_eq!(env.get("EMPTY"), Some(&Some("".to_string())));
+    assert_eq!(env.get("UNSET"), Some(&None));
+    assert_eq!(env.len(), 7);
+}
+
+#[test]
+fn test_parse_environment() {
+    let mapping: Value =
+        serde_yaml::from_str("{RACK_ENV: development, PORT: 8080, DEBUG: true, EMPTY: null}")
+            .unwrap();
+    let env = parse_environment(&mapping).unwrap();
+    assert_eq!(env.get("RACK_ENV"), Some(&Some("development".to_string())));
+    assert_eq!(env.get("PORT"), Some(&Some("8080".to_string())));
+    assert_eq!(env.get("DEBUG"), Some(&Some("true".to_string())));
+    assert_eq!(env.get("EMPTY"), Some(&None));
+
+    let list: Value = serde_yaml::from_str("[RACK_ENV=development, URL=a=b, EMPTY]").unwrap();
+    let env = parse_environment(&list).unwrap();
+    assert_eq!(env.get("RACK_ENV"), Some(&Some("development".to_string())));
+    assert_eq!(env.get("URL"), Some(&Some("a=b".to_string())));
+    assert_eq!(env.get("EMPTY"), Some(&None));
+}
+
+#[test]
+fn test_redaction() {
+    let patterns = vec!["*PASSWORD*".to_string(), "*token".to_string()];
+    assert!(is_redacted("POSTGRES_PASSWORD", &patterns));
+    assert!(is_redacted("password", &patterns));
+    assert!(is_redacted("GITHUB_TOKEN", &patterns));
+    assert!(!is_redacted("TOKEN_TTL", &patterns));
+    assert!(!is_redacted("POSTGRES_USER", &patterns));
+    assert_eq!(redact("DB_PASSWORD", "secret", &patterns), REDACTED_VALUE);
+    assert_eq!(redact("DB_USER", "admin", &patterns), "admin");
+}
+
+#[test]
+fn test_interpolate() {
+    let variables: HashMap<String, String> = [("TAG", "1.2.3"), ("EMPTY", ""), ("PORT", "5432")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let interpolated = |input: &str| interpolate(input, &variables).unwrap();
+    assert_eq!(interpolated("postgres:$TAG"), "postgres:1.2.3");
+    assert_eq!(
+        interpolated("postgres:${TAG}-alpine"),
+        "postgres:1.2.3-alpine"
+    );
+    assert_eq!(interpolated("${UNSET}"), "");
+    assert_eq!(interpolated("${UNSET:-latest}"), "latest");
+    assert_eq!(interpolated("${EMPTY:-latest}"), "latest");
+    assert_eq!(interpolated("${EMPTY-latest}"), "");
+    assert_eq!(interpolated("${UNSET-latest}"), "latest");
+    assert_eq!(interpolated("${UNSET:-${PORT}}:5432"), "5432:5432");
+    assert_eq!(interpolated("${TAG:+set}"), "set");
+    assert_eq!(interpolated("${EMPTY:+set}"), "");
+    assert_eq!(interpolated("${EMPTY+set}"), "set");
+    assert_eq!(interpolated("${TAG:?tag is required}"), "1.2.3");
+    assert_eq!(interpolated("${EMPTY?required}"), "");
+    assert_eq!(interpolated("$$TAG costs $$5"), "$TAG costs $5");
+    assert_eq!(interpolated("$1 and ${unclosed"), "$1 and ${unclosed");
+    assert_eq!(interpolated("nginx:${TAG:é}"), "nginx:${TAG:é}");
+    assert_eq!(interpolated("${é}"), "${é}");
+
+    match interpolate("${UNSET:?image tag is required}", &variables) {
+        Err(RequiredVariableMissing { name, msg }) => {
+            assert_eq!(name, "UNSET");
+            assert_eq!(msg, "image tag is required");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(interpolate("${EMPTY:?required}", &variables).is_err());
+}
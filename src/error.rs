@@ -13,4 +13,18 @@ pub enum ExcalidockerError {
     RemoteFileFailedRead { path: String, msg: String },
     #[error("Failed to parse provided docker-compose '{}'. Details: {}", path, msg)]
     InvalidDockerCompose { path: String, msg: String },
+    #[error("Required variable '{}' is missing a value. Details: {}", name, msg)]
+    RequiredVariableMissing { name: String, msg: String },
+    #[error("Failed to resolve reference in '{}'. Details: {}", path, msg)]
+    UnresolvedReference { path: String, msg: String },
+    #[error("Circular reference detected in '{}'. Details: {}", path, msg)]
+    CircularReference { path: String, msg: String },
+    #[error(
+        "Failed to parse provided excalidraw file '{}'. Details: {}",
+        path,
+        msg
+    )]
+    InvalidExcalidrawFile { path: String, msg: String },
+    #[error("Failed to write '{}'. Details: {}", path, msg)]
+    FileFailedWrite { path: String, msg: String },
 }
@@ -1,17 +1,22 @@
-use serde::Serialize;
-use serde_json::{Map, Value};
+use std::collections::HashSet;
 
-use super::excalidraw_config::{consts::NON_LOCKED, BoundElement, Roundness};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::excalidraw_config::{consts::NON_LOCKED, text_bounded_element, BoundElement, Roundness};
 use crate::exporters::excalidraw_config::{roundness, Binding};
+use crate::exporters::text_metrics::{measure_text, wrap_text};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExcalidrawFile {
     pub r#type: String,
     pub version: i32,
     pub source: Option<String>,
     pub elements: Vec<Element>,
+    #[serde(default)]
     pub app_state: AppState,
+    #[serde(default)]
     pub files: Map<String, Value>,
 }
 
@@ -28,11 +33,14 @@ impl Default for ExcalidrawFile {
     }
 }
 
-#[derive(Serialize)]
+/// Elements generated by excalidocker, the elements of other types (or drawn by hand
+/// with the attributes unknown to excalidocker) are kept as is in `Other`.
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Element {
     #[serde(rename_all = "camelCase")]
     Text {
+        id: String,
         x: i32,
         y: i32,
         width: i32,
@@ -47,13 +55,18 @@ pub enum Element {
         roughness: i32,
         opacity: i32,
         stroke_sharpness: String,
+        seed: i32,
         locked: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        custom_data: Option<Value>,
         text: String,
+        original_text: String,
         font_size: i32,
         font_family: i32,
         text_align: String,
         vertical_align: String,
         baseline: i32,
+        container_id: Option<String>,
     },
     #[serde(rename_all = "camelCase")]
     Arrow {
@@ -73,8 +86,12 @@ pub enum Element {
         opacity: i32,
         start_binding: Binding,
         end_binding: Binding,
+        bound_elements: Vec<BoundElement>,
         stroke_sharpness: String,
+        seed: i32,
         locked: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        custom_data: Option<Value>,
         points: Vec<[i32; 2]>,
     },
     #[serde(rename_all = "camelCase")]
@@ -96,7 +113,10 @@ pub enum Element {
         roundness: Option<Roundness>,
         opacity: i32,
         stroke_sharpness: String,
+        seed: i32,
         locked: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        custom_data: Option<Value>,
     },
     #[serde(rename_all = "camelCase")]
     Ellipse {
@@ -116,8 +136,13 @@ pub enum Element {
         roughness: i32,
         opacity: i32,
         stroke_sharpness: String,
+        seed: i32,
         locked: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        custom_data: Option<Value>,
     },
+    #[serde(untagged)]
+    Other(Value),
 }
 
 pub mod elements {
@@ -132,16 +157,22 @@ pub mod elements {
     pub const OPACITY: i32 = 100;
     pub const STROKE_SHARPNESS: &str = "sharp";
     pub const FONT_SIZE_SMALL: i32 = 16;
-    pub const FONT_SIZE_MEDIUM: i32 = 20;
-    pub const FONT_SIZE_LARGE: i32 = 28;
-    pub const FONT_SIZE_EXTRA_LARGE: i32 = 36;
     pub const TEXT_ALIGN_LEFT: &str = "left";
+    pub const TEXT_ALIGN_CENTER: &str = "center";
     pub const VERTICAL_ALIGN_TOP: &str = "top";
+    pub const VERTICAL_ALIGN_MIDDLE: &str = "middle";
+    /// Gap between the shape and its label, the same as Excalidraw keeps
+    pub const BOUND_TEXT_PADDING: i32 = 5;
+    /// The labels of the arrows are wrapped to the part of the arrow width,
+    /// but not narrower than the font size multiplied by the ratio (as Excalidraw does)
+    pub const ARROW_LABEL_WIDTH_FRACTION: f64 = 0.7;
+    pub const ARROW_LABEL_MIN_WIDTH_RATIO: i32 = 11;
 }
 
 #[allow(clippy::too_many_arguments)]
 impl Element {
     pub fn text(
+        id: String,
         x: i32,
         y: i32,
         width: i32,
@@ -160,8 +191,10 @@ impl Element {
         font_family: i32,
         text_align: String,
         vertical_align: String,
+        container_id: Option<String>,
     ) -> Self {
         Self::Text {
+            id,
             x,
             y,
             width,
@@ -176,13 +209,17 @@ impl Element {
             roughness: 0,
             opacity,
             stroke_sharpness,
+            seed: 0,
             locked: NON_LOCKED,
+            custom_data: None,
+            original_text: text.clone(),
             text,
             font_size,
             font_family,
             text_align,
             vertical_align,
             baseline: 15,
+            container_id,
         }
     }
 
@@ -213,6 +250,7 @@ impl Element {
             height,
             start_binding,
             end_binding,
+            bound_elements: Vec::new(),
             angle,
             stroke_color,
             background_color,
@@ -223,7 +261,9 @@ impl Element {
             roughness: 2, // roughness: 0
             opacity,
             stroke_sharpness,
+            seed: 0,
             locked: NON_LOCKED,
+            custom_data: None,
             points,
         }
     }
@@ -264,7 +304,9 @@ impl Element {
             roundness,
             opacity,
             stroke_sharpness,
+            seed: 0,
             locked: NON_LOCKED,
+            custom_data: None,
         }
     }
 
@@ -302,7 +344,9 @@ impl Element {
             roughness: 1, // roughness: 0
             opacity,
             stroke_sharpness,
+            seed: 0,
             locked: NON_LOCKED,
+            custom_data: None,
         }
     }
 
@@ -337,6 +381,7 @@ impl Element {
     }
 
     pub fn draw_small_monospaced_text(
+        id: String,
         text: String,
         x: i32,
         y: i32,
@@ -344,11 +389,13 @@ impl Element {
         font_size: i32,
         font_family: i32,
     ) -> Self {
+        let (width, height) = measure_text(&text, font_size, font_family);
         Self::text(
+            id,
             x,
             y,
-            (4 + text.chars().count() * 18) as i32,
-            (text.lines().count() * 19) as i32,
+            width,
+            height,
             group_ids,
             0,
             elements::STROKE_COLOR.into(),
@@ -363,15 +410,173 @@ impl Element {
             font_family, //elements::FONT_FAMILY_MONOSPACE,
             elements::TEXT_ALIGN_LEFT.into(),
             elements::VERTICAL_ALIGN_TOP.into(),
+            None,
+        )
+    }
+
+    /// Label bound to the shape the same way Excalidraw binds the labels:
+    /// centered and wrapped to the width of the shape. The label of the rectangle or ellipse is
+    /// aligned vertically by `vertical_align` (`top` or `middle`), the shape grows to fit the label.
+    /// The label of the arrow is placed over its middle point (or the middle of its middle segment).
+    /// The shape holds the label in its bound elements.
+    pub fn bound_text(
+        id: String,
+        text: String,
+        container: &mut Element,
+        vertical_align: &str,
+        font_size: i32,
+        font_family: i32,
+    ) -> Self {
+        let (container_id, x, y, width, height, group_ids, bound_elements, is_ellipse) =
+            match container {
+                Self::Rectangle {
+                    id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    group_ids,
+                    bound_elements,
+                    ..
+                } => (id, x, y, width, height, group_ids, bound_elements, false),
+                Self::Ellipse {
+                    id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    group_ids,
+                    bound_elements,
+                    ..
+                } => (id, x, y, width, height, group_ids, bound_elements, true),
+                Self::Arrow {
+                    id: arrow_id,
+                    x,
+                    y,
+                    width,
+                    points,
+                    bound_elements,
+                    ..
+                } => {
+                    let max_width = ((*width as f64 * elements::ARROW_LABEL_WIDTH_FRACTION) as i32)
+                        .max(elements::ARROW_LABEL_MIN_WIDTH_RATIO * font_size);
+                    let wrapped = wrap_text(&text, max_width, font_size, font_family);
+                    let (text_width, text_height) = measure_text(&wrapped, font_size, font_family);
+                    let point = |index: usize| points.get(index).copied().unwrap_or([0, 0]);
+                    let middle = points.len() / 2;
+                    let [middle_x, middle_y] = if points.len() % 2 == 1 {
+                        point(middle)
+                    } else {
+                        let ([x1, y1], [x2, y2]) = (point(middle.max(1) - 1), point(middle));
+                        [(x1 + x2) / 2, (y1 + y2) / 2]
+                    };
+                    bound_elements.push(text_bounded_element(id.clone()));
+                    return Self::label(
+                        id,
+                        text,
+                        wrapped,
+                        (
+                            *x + middle_x - text_width / 2,
+                            *y + middle_y - text_height / 2,
+                        ),
+                        (text_width, text_height),
+                        Vec::new(),
+                        elements::VERTICAL_ALIGN_MIDDLE,
+                        arrow_id.clone(),
+                        font_size,
+                        font_family,
+                    );
+                }
+                Self::Text { .. } | Self::Other(_) => {
+                    unreachable!("labels are bound to rectangles, ellipses and arrows only")
+                }
+            };
+        // the label of the ellipse fits the square inscribed into it
+        let inner_offset = |size: i32| {
+            if is_ellipse {
+                (size as f64 / 2.0 * (1.0 - std::f64::consts::FRAC_1_SQRT_2)).round() as i32
+            } else {
+                0
+            }
+        };
+        let max_width = *width - 2 * inner_offset(*width) - 2 * elements::BOUND_TEXT_PADDING;
+        let wrapped = wrap_text(&text, max_width, font_size, font_family);
+        let (text_width, text_height) = measure_text(&wrapped, font_size, font_family);
+        let min_height = text_height + 2 * elements::BOUND_TEXT_PADDING;
+        *height = (*height).max(if is_ellipse {
+            (min_height as f64 * std::f64::consts::SQRT_2).ceil() as i32
+        } else {
+            min_height
+        });
+        let text_y = match vertical_align {
+            elements::VERTICAL_ALIGN_TOP => {
+                *y + inner_offset(*height) + elements::BOUND_TEXT_PADDING
+            }
+            _ => *y + (*height - text_height) / 2,
+        };
+        bound_elements.push(text_bounded_element(id.clone()));
+        Self::label(
+            id,
+            text,
+            wrapped,
+            (*x + (*width - text_width) / 2, text_y),
+            (text_width, text_height),
+            group_ids.clone(),
+            vertical_align,
+            container_id.clone(),
+            font_size,
+            font_family,
         )
     }
 
+    /// Centered text bound to the container, `text` is kept as the original one to rewrap it on resize
+    fn label(
+        id: String,
+        text: String,
+        wrapped: String,
+        (x, y): (i32, i32),
+        (width, height): (i32, i32),
+        group_ids: Vec<String>,
+        vertical_align: &str,
+        container_id: String,
+        font_size: i32,
+        font_family: i32,
+    ) -> Self {
+        let mut label = Self::text(
+            id,
+            x,
+            y,
+            width,
+            height,
+            group_ids,
+            0,
+            elements::STROKE_COLOR.into(),
+            elements::BACKGROUND_COLOR.into(),
+            elements::FILL_STYLE.into(),
+            elements::STROKE_WIDTH,
+            elements::STROKE_STYLE.into(),
+            elements::OPACITY,
+            elements::STROKE_SHARPNESS.into(),
+            wrapped,
+            font_size,
+            font_family,
+            elements::TEXT_ALIGN_CENTER.into(),
+            vertical_align.into(),
+            Some(container_id),
+        );
+        if let Self::Text { original_text, .. } = &mut label {
+            *original_text = text;
+        }
+        label
+    }
+
     pub fn simple_arrow(
         id: String,
         x: i32,
         y: i32,
         width: i32,
         height: i32,
+        stroke_color: String,
         stroke_style: String,
         edge: String,
         points: Vec<[i32; 2]>,
@@ -387,7 +592,7 @@ impl Element {
             start_binding,
             end_binding,
             elements::ANGLE,
-            elements::STROKE_COLOR.into(),
+            stroke_color,
             elements::BACKGROUND_COLOR.into(),
             elements::FILL_STYLE.into(),
             elements::STROKE_WIDTH,
@@ -433,8 +638,205 @@ impl Element {
     }
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
+/// Key of the `customData` marking the elements generated by excalidocker
+const GENERATED_MARKER: &str = "excalidocker";
+
+impl Element {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Text { id, .. }
+            | Self::Arrow { id, .. }
+            | Self::Rectangle { id, .. }
+            | Self::Ellipse { id, .. } => Some(id),
+            Self::Other(value) => value["id"].as_str(),
+        }
+    }
+
+    /// Marks the element as generated, the rectangles of the services also hold the service name.
+    /// The marker survives editing in Excalidraw and allows telling the generated elements
+    /// apart from the ones added by hand when the drawing is updated.
+    pub fn mark_generated(&mut self, service: Option<&str>) {
+        let marker = match service {
+            Some(service) => json!({ GENERATED_MARKER: { "service": service } }),
+            None => json!({ GENERATED_MARKER: {} }),
+        };
+        match self {
+            Self::Text { custom_data, .. }
+            | Self::Arrow { custom_data, .. }
+            | Self::Rectangle { custom_data, .. }
+            | Self::Ellipse { custom_data, .. } => *custom_data = Some(marker),
+            Self::Other(value) => value["customData"] = marker,
+        }
+    }
+
+    /// Seed of the hand-drawn strokes; Excalidraw picks a random one when it is missing,
+    /// thus it is set explicitly to render the same drawing every time
+    pub fn set_seed(&mut self, value: i32) {
+        match self {
+            Self::Text { seed, .. }
+            | Self::Arrow { seed, .. }
+            | Self::Rectangle { seed, .. }
+            | Self::Ellipse { seed, .. } => *seed = value,
+            Self::Other(element) => element["seed"] = value.into(),
+        }
+    }
+
+    pub fn add_bound_element(&mut self, bound_element: BoundElement) {
+        match self {
+            Self::Rectangle { bound_elements, .. }
+            | Self::Ellipse { bound_elements, .. }
+            | Self::Arrow { bound_elements, .. } => {
+                if !bound_elements.contains(&bound_element) {
+                    bound_elements.push(bound_element);
+                }
+            }
+            Self::Text { .. } | Self::Other(_) => {}
+        }
+    }
+}
+
+/// Whether the (serialized) element was generated by excalidocker
+pub fn is_generated(element: &Value) -> bool {
+    !element["customData"][GENERATED_MARKER].is_null()
+}
+
+/// Prefixes of the ids given to the generated elements and groups
+const GENERATED_ID_PREFIXES: [&str; 8] = [
+    "container_",
+    "ellipse_",
+    "port_arrow_",
+    "connecting_arrow_",
+    "network_rectangle_",
+    "group_",
+    "volume_",
+    "bind_mount_",
+];
+
+/// The drawings created before the elements were marked are told apart by the ids:
+/// the generated texts have no id and the other elements have the known id prefixes
+/// or belong to the generated groups.
+fn is_legacy_generated(element: &Value) -> bool {
+    let is_generated_id = |id: &str| {
+        GENERATED_ID_PREFIXES
+            .iter()
+            .any(|prefix| id.starts_with(prefix))
+    };
+    match element["id"].as_str() {
+        None => true,
+        Some(id) => {
+            is_generated_id(id)
+                || element["groupIds"].as_array().is_some_and(|group_ids| {
+                    group_ids
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .any(is_generated_id)
+                })
+        }
+    }
+}
+
+impl ExcalidrawFile {
+    /// Serialized elements, convenient for inspecting the elements of any type
+    pub fn element_values(&self) -> Vec<Value> {
+        self.elements
+            .iter()
+            .filter_map(|element| serde_json::to_value(element).ok())
+            .collect()
+    }
+
+    /// Replaces the elements generated by the previous run in the `existing` drawing with the
+    /// elements of this file. The elements added by hand are kept along with their bindings
+    /// to the services which still exist, the bindings to the removed elements are dropped.
+    pub fn update(mut self, existing: ExcalidrawFile) -> ExcalidrawFile {
+        let existing_elements = existing.element_values();
+        let is_legacy = !existing_elements.iter().any(is_generated);
+        let user_elements = existing_elements
+            .into_iter()
+            .filter(|element| {
+                let generated = if is_legacy {
+                    is_legacy_generated(element)
+                } else {
+                    is_generated(element)
+                };
+                !generated && !element["isDeleted"].as_bool().unwrap_or(false)
+            })
+            .collect::<Vec<Value>>();
+        let generated_ids = self
+            .elements
+            .iter()
+            .filter_map(|element| element.id().map(str::to_string))
+            .collect::<HashSet<String>>();
+        // a shape holds a single label, the texts added by hand to the labeled shapes are unbound
+        let labeled_ids = self
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                Element::Text {
+                    container_id: Some(container_id),
+                    ..
+                } => Some(container_id.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<String>>();
+        let known_ids = user_elements
+            .iter()
+            .filter_map(|element| element["id"].as_str().map(str::to_string))
+            .chain(generated_ids.iter().cloned())
+            .collect::<HashSet<String>>();
+        let mut user_elements = user_elements;
+        // (generated element id, element bound to it)
+        let mut bindings = vec![];
+        for element in user_elements.iter_mut() {
+            let element_id = element["id"].as_str().unwrap_or_default().to_string();
+            for binding in ["startBinding", "endBinding"] {
+                if let Some(bound_id) = element[binding]["elementId"].as_str() {
+                    if !known_ids.contains(bound_id) {
+                        element[binding] = Value::Null;
+                    } else if generated_ids.contains(bound_id) {
+                        bindings.push((bound_id.to_string(), element_id.clone(), "arrow"));
+                    }
+                }
+            }
+            if let Some(container_id) = element["containerId"].as_str() {
+                if !known_ids.contains(container_id) || labeled_ids.contains(container_id) {
+                    element["containerId"] = Value::Null;
+                } else if generated_ids.contains(container_id) {
+                    bindings.push((container_id.to_string(), element_id.clone(), "text"));
+                }
+            }
+            if let Some(bound_elements) = element["boundElements"].as_array_mut() {
+                bound_elements.retain(|bound| {
+                    bound["id"]
+                        .as_str()
+                        .is_some_and(|bound_id| known_ids.contains(bound_id))
+                });
+            }
+        }
+        for (generated_id, user_id, element_type) in bindings {
+            if let Some(generated) = self
+                .elements
+                .iter_mut()
+                .find(|element| element.id() == Some(generated_id.as_str()))
+            {
+                generated.add_bound_element(BoundElement {
+                    id: user_id,
+                    element_type: element_type.to_string(),
+                });
+            }
+        }
+        // the elements added by hand are drawn on top of the generated ones
+        self.elements
+            .extend(user_elements.into_iter().map(Element::Other));
+        ExcalidrawFile {
+            app_state: existing.app_state,
+            files: existing.files,
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct AppState {
     pub grid_size: i32,
     pub view_background_color: String,
@@ -448,3 +850,171 @@ impl Default for AppState {
         }
     }
 }
+
+#[test]
+fn test_update_drawing() {
+    let existing: ExcalidrawFile = serde_json::from_value(json!({
+        "type": "excalidraw",
+        "version": 2,
+        "source": "https://excalidraw.com",
+        "elements": [
+            {"type": "rectangle", "id": "container_app", "x": 0, "y": 0,
+             "customData": {"excalidocker": {"service": "app"}}},
+            {"type": "rectangle", "id": "container_db", "x": 200, "y": 0,
+             "customData": {"excalidocker": {"service": "db"}}},
+            {"type": "arrow", "id": "note_arrow", "x": 0, "y": 0,
+             "startBinding": {"elementId": "container_app", "focus": 0.0, "gap": 1},
+             "endBinding": {"elementId": "container_db", "focus": 0.0, "gap": 1}},
+            {"type": "text", "id": "removed_note", "isDeleted": true},
+        ],
+        "appState": {"gridSize": 20, "viewBackgroundColor": "#fafafa"},
+    }))
+    .unwrap();
+    let mut app = Element::simple_rectangle(
+        "container_app".into(),
+        40,
+        40,
+        140,
+        60,
+        Vec::new(),
+        Vec::new(),
+        "transparent".into(),
+        "hachure".into(),
+        "solid".into(),
+        "round".into(),
+    );
+    app.mark_generated(Some("app"));
+    let generated = ExcalidrawFile {
+        elements: vec![app],
+        ..ExcalidrawFile::default()
+    };
+    let updated = generated.update(existing).element_values();
+    // the regenerated service is kept, the removed one and the deleted note are dropped
+    assert_eq!(2, updated.len());
+    assert_eq!(40, updated[0]["x"]);
+    assert_eq!("note_arrow", updated[0]["boundElements"][0]["id"]);
+    assert_eq!("note_arrow", updated[1]["id"]);
+    assert_eq!("container_app", updated[1]["startBinding"]["elementId"]);
+    assert!(updated[1]["endBinding"].is_null());
+
+    // the drawings without the markers are updated by the generated ids
+    let legacy: ExcalidrawFile = serde_json::from_value(json!({
+        "type": "excalidraw",
+        "version": 2,
+        "source": null,
+        "elements": [
+            {"type": "rectangle", "id": "container_app", "groupIds": ["container_group_x"]},
+            {"type": "rectangle", "id": "replica", "groupIds": ["container_group_x"]},
+            {"type": "text", "text": "app"},
+            {"type": "freedraw", "id": "scribble", "groupIds": []},
+        ],
+    }))
+    .unwrap();
+    let updated = ExcalidrawFile::default().update(legacy).element_values();
+    assert_eq!(1, updated.len());
+    assert_eq!("scribble", updated[0]["id"]);
+}
+
+#[test]
+fn test_bound_text() {
+    let mut rectangle = Element::simple_rectangle(
+        "container_app".into(),
+        0,
+        0,
+        140,
+        60,
+        vec!["container_group".into()],
+        Vec::new(),
+        "transparent".into(),
+        "hachure".into(),
+        "solid".into(),
+        "round".into(),
+    );
+    let label = Element::bound_text(
+        "text_app".into(),
+        "app".into(),
+        &mut rectangle,
+        elements::VERTICAL_ALIGN_MIDDLE,
+        20,
+        3,
+    );
+    let (label, rectangle) = (
+        serde_json::to_value(label).unwrap(),
+        serde_json::to_value(rectangle).unwrap(),
+    );
+    assert_eq!("container_app", label["containerId"]);
+    assert_eq!(json!(["container_group"]), label["groupIds"]);
+    assert_eq!(
+        json!([{"id": "text_app", "type": "text"}]),
+        rectangle["boundElements"]
+    );
+    // centered in the rectangle
+    assert_eq!(
+        (52, 18),
+        (label["x"].as_i64().unwrap(), label["y"].as_i64().unwrap())
+    );
+
+    // the label is wrapped to the width of the ellipse, the ellipse grows to fit it
+    let mut ellipse = Element::draw_ellipse(
+        "ellipse".into(),
+        0,
+        0,
+        100,
+        60,
+        Vec::new(),
+        Vec::new(),
+        "transparent".into(),
+        "hachure".into(),
+    );
+    let label = Element::bound_text(
+        "text_port".into(),
+        "8080 9090".into(),
+        &mut ellipse,
+        elements::VERTICAL_ALIGN_MIDDLE,
+        20,
+        3,
+    );
+    let (label, ellipse) = (
+        serde_json::to_value(label).unwrap(),
+        serde_json::to_value(ellipse).unwrap(),
+    );
+    assert_eq!("8080\n9090", label["text"]);
+    assert_eq!("8080 9090", label["originalText"]);
+    assert!(ellipse["height"].as_i64().unwrap() > 60);
+
+    // the label of the arrow is placed over the middle of its middle segment
+    let mut arrow = Element::simple_arrow(
+        "arrow".into(),
+        100,
+        100,
+        200,
+        100,
+        "#000000".into(),
+        "solid".into(),
+        "sharp".into(),
+        vec![[0, 0], [0, 100], [200, 100], [200, 0]],
+        crate::exporters::excalidraw_config::binding("app".into()),
+        crate::exporters::excalidraw_config::binding("db".into()),
+    );
+    let label = Element::bound_text(
+        "text_arrow".into(),
+        "links".into(),
+        &mut arrow,
+        elements::VERTICAL_ALIGN_MIDDLE,
+        20,
+        3,
+    );
+    let (label, arrow) = (
+        serde_json::to_value(label).unwrap(),
+        serde_json::to_value(arrow).unwrap(),
+    );
+    assert_eq!("arrow", label["containerId"]);
+    assert_eq!(
+        json!([{"id": "text_arrow", "type": "text"}]),
+        arrow["boundElements"]
+    );
+    assert_eq!(
+        (171, 188),
+        (label["x"].as_i64().unwrap(), label["y"].as_i64().unwrap())
+    );
+}
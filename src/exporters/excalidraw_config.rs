@@ -20,13 +20,102 @@ services:
 ports:
   background_color: "#a5d8ff"
   fill: "hachure"
+  label: true
 connections:
   visible: true
   edge: "sharp"
+  depends_on:
+    visible: true
+    stroke_color: "#000000"
+    stroke_style: "dashed"
+    label: false
+  links:
+    visible: true
+    stroke_color: "#1971c2"
+    stroke_style: "dotted"
+    label: true
+  volumes_from:
+    visible: true
+    stroke_color: "#e8590c"
+    stroke_style: "dashed"
+    label: true
+  network_mode:
+    visible: true
+    stroke_color: "#2f9e44"
+    stroke_style: "solid"
+    label: true
+  ipc:
+    visible: true
+    stroke_color: "#9c36b5"
+    stroke_style: "dotted"
+    label: true
+  pid:
+    visible: true
+    stroke_color: "#c2255c"
+    stroke_style: "dotted"
+    label: true
+  conditions:
+    label: true
+    service_healthy: "#2f9e44"
+    service_completed_successfully: "#1971c2"
+    optional_stroke_style: "dotted"
 alignment:
   mode: "stepped"
+  direction: "top-down"
+  seed: 42
+  iterations: 300
+  force_networks: true
+  force_volumes: true
+  columns: 4
+  row_gap: 60
+  column_gap: 60
+  sort_by: "traversal"
+  group_by: "network"
+  group_label: "com.docker.compose.group"
+  group_separator: "-"
 network:
   visible: true
+  default_network: false
+  details: true
+  addresses: true
+volumes:
+  visible: true
+  background_color: "#ffec99"
+  fill: "hachure"
+  edge: "round"
+  bind_mounts: false
+  bind_mount_background_color: "#ffd8a8"
+secrets:
+  visible: true
+  background_color: "#ffc9c9"
+  fill: "hachure"
+  edge: "round"
+configs:
+  visible: true
+  background_color: "#d0bfff"
+  fill: "hachure"
+  edge: "round"
+profiles:
+  visible: false
+  background_color: "#e9ecef"
+  stroke_style: "dashed"
+environment:
+  visible: false
+  redacted_keys:
+    - "*PASSWORD*"
+    - "*PASSWD*"
+    - "*SECRET*"
+    - "*TOKEN*"
+    - "*API_KEY*"
+    - "*PRIVATE_KEY*"
+    - "*CREDENTIAL*"
+healthcheck:
+  visible: true
+  background_color: "#2f9e44"
+deploy:
+  replicas: true
+  resources: false
+  placement: false
 "###;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -37,6 +126,20 @@ pub struct ExcalidrawConfig {
     pub connections: Connections,
     pub alignment: Alignment,
     pub network: Network,
+    #[serde(default)]
+    pub volumes: Volumes,
+    #[serde(default = "Resources::secrets")]
+    pub secrets: Resources,
+    #[serde(default = "Resources::configs")]
+    pub configs: Resources,
+    #[serde(default)]
+    pub profiles: Profiles,
+    #[serde(default)]
+    pub environment: Environment,
+    #[serde(default)]
+    pub healthcheck: Healthcheck,
+    #[serde(default)]
+    pub deploy: Deploy,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -58,25 +161,356 @@ pub struct Ports {
     #[serde(serialize_with = "serialize_background_color")]
     pub background_color: String,
     pub fill: String,
+    /// label the port arrow with the container port when it differs from the host one
+    #[serde(default = "enabled")]
+    pub label: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Connections {
     pub visible: bool,
     pub edge: String,
+    #[serde(default = "ConnectionStyle::depends_on")]
+    pub depends_on: ConnectionStyle,
+    #[serde(default = "ConnectionStyle::links")]
+    pub links: ConnectionStyle,
+    #[serde(default = "ConnectionStyle::volumes_from")]
+    pub volumes_from: ConnectionStyle,
+    #[serde(default = "ConnectionStyle::network_mode")]
+    pub network_mode: ConnectionStyle,
+    #[serde(default = "ConnectionStyle::ipc")]
+    pub ipc: ConnectionStyle,
+    #[serde(default = "ConnectionStyle::pid")]
+    pub pid: ConnectionStyle,
+    #[serde(default)]
+    pub conditions: Conditions,
+}
+
+/// Style of the `depends_on` connections declared with the long syntax.
+/// Arrows get the `stroke_color` of their condition, the optional (`required: false`) ones
+/// get the `optional_stroke_style`. Non-default condition and flags are rendered next to the arrow when `label`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Conditions {
+    pub label: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub service_healthy: String,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub service_completed_successfully: String,
+    pub optional_stroke_style: String,
+}
+
+impl Conditions {
+    /// Stroke color for the given condition, `service_started` keeps the `depends_on` color
+    pub fn stroke_color(&self, condition: &str) -> Option<&String> {
+        match condition {
+            "service_healthy" => Some(&self.service_healthy),
+            "service_completed_successfully" => Some(&self.service_completed_successfully),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Conditions {
+    fn default() -> Self {
+        Self {
+            label: true,
+            service_healthy: "#2f9e44".to_string(),
+            service_completed_successfully: "#1971c2".to_string(),
+            optional_stroke_style: "dotted".to_string(),
+        }
+    }
+}
+
+/// Services defining a `healthcheck` are marked with a small circle when `visible`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Healthcheck {
+    pub visible: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub background_color: String,
+}
+
+impl Default for Healthcheck {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            background_color: "#2f9e44".to_string(),
+        }
+    }
+}
+
+/// Style of the connection (arrow) of a particular kind.
+/// The kind name is rendered next to the arrow when `label` is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConnectionStyle {
+    pub visible: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub stroke_color: String,
+    pub stroke_style: String,
+    pub label: bool,
+}
+
+impl ConnectionStyle {
+    fn depends_on() -> Self {
+        Self::new("#000000", "dashed", false)
+    }
+
+    fn links() -> Self {
+        Self::new("#1971c2", "dotted", true)
+    }
+
+    fn volumes_from() -> Self {
+        Self::new("#e8590c", "dashed", true)
+    }
+
+    fn network_mode() -> Self {
+        Self::new("#2f9e44", "solid", true)
+    }
+
+    fn ipc() -> Self {
+        Self::new("#9c36b5", "dotted", true)
+    }
+
+    fn pid() -> Self {
+        Self::new("#c2255c", "dotted", true)
+    }
+
+    fn new(stroke_color: &str, stroke_style: &str, label: bool) -> Self {
+        Self {
+            visible: true,
+            stroke_color: stroke_color.to_string(),
+            stroke_style: stroke_style.to_string(),
+            label,
+        }
+    }
 }
 
+/// Networks are rendered as boundaries around their members when `visible`.
+/// The implicit `<project>_default` network (every service without explicit `networks` joins it)
+/// would wrap the whole diagram, so it's rendered only when `default_network` is enabled.
+/// The network label includes the driver and IPAM configuration when `details`,
+/// the service card lists its static addresses and aliases when `addresses`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Network {
     pub visible: bool,
+    #[serde(default)]
+    pub default_network: bool,
+    #[serde(default = "enabled")]
+    pub details: bool,
+    #[serde(default = "enabled")]
+    pub addresses: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Named volumes are rendered as separate rectangles connected to the services mounting them.
+/// Host paths of bind mounts are rendered only when `bind_mounts` is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Volumes {
+    pub visible: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub background_color: String,
+    pub fill: String,
+    pub edge: String,
+    pub bind_mounts: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub bind_mount_background_color: String,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            background_color: "#ffec99".to_string(),
+            fill: "hachure".to_string(),
+            edge: "round".to_string(),
+            bind_mounts: false,
+            bind_mount_background_color: "#ffd8a8".to_string(),
+        }
+    }
 }
 
+/// Secrets and configs are rendered as separate rectangles connected to the services consuming them.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Resources {
+    pub visible: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub background_color: String,
+    pub fill: String,
+    pub edge: String,
+}
+
+impl Resources {
+    fn secrets() -> Self {
+        Self::visible_with_background_color("#ffc9c9")
+    }
+
+    fn configs() -> Self {
+        Self::visible_with_background_color("#d0bfff")
+    }
+
+    fn visible_with_background_color(background_color: &str) -> Self {
+        Self {
+            visible: true,
+            background_color: background_color.to_string(),
+            fill: "hachure".to_string(),
+            edge: "round".to_string(),
+        }
+    }
+}
+
+/// Profile-gated services are rendered with the distinct style and the profile label when `visible`
+/// (regardless of the active profiles), otherwise the services of inactive profiles are not rendered.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Profiles {
+    pub visible: bool,
+    #[serde(serialize_with = "serialize_background_color")]
+    pub background_color: String,
+    pub stroke_style: String,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            background_color: "#e9ecef".to_string(),
+            stroke_style: "dashed".to_string(),
+        }
+    }
+}
+
+/// Replicated services (`deploy.replicas`, `scale` or the global mode) are rendered
+/// as stacked cards with the replicas badge when `replicas`.
+/// Resource limits and reservations are rendered on the service card when `resources`,
+/// placement constraints when `placement`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Deploy {
+    pub replicas: bool,
+    pub resources: bool,
+    #[serde(default)]
+    pub placement: bool,
+}
+
+impl Default for Deploy {
+    fn default() -> Self {
+        Self {
+            replicas: true,
+            resources: false,
+            placement: false,
+        }
+    }
+}
+
+/// Environment variables are rendered on the service card when `visible`.
+/// Values of the variables with keys matching any of `redacted_keys` patterns are masked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Environment {
+    pub visible: bool,
+    #[serde(default = "default_redacted_keys")]
+    pub redacted_keys: Vec<String>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            redacted_keys: default_redacted_keys(),
+        }
+    }
+}
+
+fn default_redacted_keys() -> Vec<String> {
+    [
+        "*PASSWORD*",
+        "*PASSWD*",
+        "*SECRET*",
+        "*TOKEN*",
+        "*API_KEY*",
+        "*PRIVATE_KEY*",
+        "*CREDENTIAL*",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+/// `grouped` mode clusters the services `group_by`: `network`, `label` (value of the `group_label` service label)
+/// or `prefix` (part of the service name before the `group_separator`).
+/// `layered` mode places the ranks of the dependency graph in the `direction`:
+/// `top-down`, `bottom-up`, `left-right` or `right-left`.
+/// `force` mode runs the simulation seeded with `seed` for the given number of `iterations`,
+/// networks and named volumes attract their services when `force_networks`/`force_volumes`.
+/// `grid` mode places the services sorted by `sort_by` (`traversal`, `name` or `network`)
+/// in the given number of `columns` separated by `row_gap` and `column_gap`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Alignment {
     pub mode: String,
+    #[serde(default = "Alignment::direction")]
+    pub direction: String,
+    #[serde(default = "Alignment::seed")]
+    pub seed: u64,
+    #[serde(default = "Alignment::iterations")]
+    pub iterations: usize,
+    #[serde(default = "enabled")]
+    pub force_networks: bool,
+    #[serde(default = "enabled")]
+    pub force_volumes: bool,
+    #[serde(default = "Alignment::columns")]
+    pub columns: usize,
+    #[serde(default = "Alignment::gap")]
+    pub row_gap: i32,
+    #[serde(default = "Alignment::gap")]
+    pub column_gap: i32,
+    #[serde(default = "Alignment::sort_by")]
+    pub sort_by: String,
+    #[serde(default = "Alignment::group_by")]
+    pub group_by: String,
+    #[serde(default = "Alignment::group_label")]
+    pub group_label: String,
+    #[serde(default = "Alignment::group_separator")]
+    pub group_separator: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl Alignment {
+    fn direction() -> String {
+        "top-down".to_string()
+    }
+
+    fn seed() -> u64 {
+        42
+    }
+
+    fn iterations() -> usize {
+        300
+    }
+
+    fn columns() -> usize {
+        4
+    }
+
+    fn gap() -> i32 {
+        60
+    }
+
+    fn sort_by() -> String {
+        "traversal".to_string()
+    }
+
+    fn group_by() -> String {
+        "network".to_string()
+    }
+
+    fn group_label() -> String {
+        "com.docker.compose.group".to_string()
+    }
+
+    fn group_separator() -> String {
+        "-".to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BoundElement {
     pub id: String,
@@ -84,7 +518,7 @@ pub struct BoundElement {
     pub element_type: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Binding {
     pub element_id: String,
@@ -92,7 +526,7 @@ pub struct Binding {
     pub gap: u16,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Roundness {
     #[serde(rename = "type")]
@@ -114,6 +548,13 @@ pub fn arrow_bounded_element(id: String) -> BoundElement {
     }
 }
 
+pub fn text_bounded_element(id: String) -> BoundElement {
+    BoundElement {
+        id,
+        element_type: "text".to_string(),
+    }
+}
+
 pub fn roundness(edge: String) -> Option<Roundness> {
     match edge.as_str() {
         "round" => Some(Roundness { roundness_type: 3 }),
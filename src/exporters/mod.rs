@@ -1,2 +1,3 @@
 pub mod excalidraw;
 pub mod excalidraw_config;
+pub mod text_metrics;
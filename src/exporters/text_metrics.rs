@@ -0,0 +1,219 @@
+//! Text measurement for the Excalidraw font families.
+//! The width of a text is the sum of the advance widths of its characters,
+//! the height is the number of lines multiplied by the line height of the family.
+
+pub const FONT_FAMILY_VIRGIL: i32 = 1;
+pub const FONT_FAMILY_HELVETICA: i32 = 2;
+pub const FONT_FAMILY_CASCADIA: i32 = 3;
+
+/// Advance widths of the printable ASCII characters (`' '..='~'`) in 1/1000 of the font size
+type AdvanceTable = [u16; 95];
+
+/// TODO take the widths from the `hmtx` table of `Virgil.woff2` shipped by Excalidraw,
+/// these are the approximations rounded to 20 units
+#[rustfmt::skip]
+const VIRGIL_ADVANCES: AdvanceTable = [
+    // space ! " # $ % & ' ( ) * + , - . /
+    320, 300, 420, 700, 620, 800, 720, 240, 360, 360, 520, 600, 260, 480, 240, 560,
+    // 0 - 9
+    620, 420, 600, 580, 620, 600, 600, 580, 600, 600,
+    // : ; < = > ? @
+    260, 280, 540, 600, 540, 560, 880,
+    // A - Z
+    700, 640, 640, 700, 600, 580, 700, 720, 380, 560, 660, 560, 860,
+    740, 740, 600, 760, 660, 620, 640, 700, 680, 960, 680, 640, 640,
+    // [ \ ] ^ _ `
+    380, 560, 380, 520, 640, 340,
+    // a - z
+    560, 560, 500, 560, 540, 420, 560, 560, 280, 340, 520, 280, 840,
+    560, 560, 560, 560, 440, 500, 420, 560, 520, 780, 540, 540, 520,
+    // { | } ~
+    400, 260, 400, 620,
+];
+
+/// Widths of the Adobe Helvetica AFM metrics
+#[rustfmt::skip]
+const HELVETICA_ADVANCES: AdvanceTable = [
+    // space ! " # $ % & ' ( ) * + , - . /
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    // 0 - 9
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556,
+    // : ; < = > ? @
+    278, 278, 584, 584, 584, 556, 1015,
+    // A - Z
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611,
+    // [ \ ] ^ _ `
+    278, 278, 278, 469, 556, 333,
+    // a - z
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833,
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500,
+    // { | } ~
+    334, 260, 334, 584,
+];
+
+/// Cascadia is monospaced
+const CASCADIA_ADVANCE: u16 = 586;
+
+/// Line heights of the families (in 1/100 of the font size), the same as Excalidraw uses
+const VIRGIL_LINE_HEIGHT: i32 = 125;
+const HELVETICA_LINE_HEIGHT: i32 = 115;
+const CASCADIA_LINE_HEIGHT: i32 = 120;
+
+/// Width and height of the (possibly multiline) text rendered with the font.
+/// Unknown font families are measured as Virgil, the default Excalidraw font.
+pub fn measure_text(text: &str, font_size: i32, font_family: i32) -> (i32, i32) {
+    let widest_line = text
+        .split('\n')
+        .map(|line| {
+            line.chars()
+                .map(|c| advance(c, font_family) as i64)
+                .sum::<i64>()
+        })
+        .max()
+        .unwrap_or(0);
+    let line_height = match font_family {
+        FONT_FAMILY_VIRGIL => VIRGIL_LINE_HEIGHT,
+        FONT_FAMILY_HELVETICA => HELVETICA_LINE_HEIGHT,
+        FONT_FAMILY_CASCADIA => CASCADIA_LINE_HEIGHT,
+        _ => VIRGIL_LINE_HEIGHT,
+    };
+    let lines = text.split('\n').count() as i64;
+    let font_size = font_size.max(0) as i64;
+    (
+        div_ceil(widest_line * font_size, 1000) as i32,
+        div_ceil(lines * font_size * line_height as i64, 100) as i32,
+    )
+}
+
+/// Wraps the text to fit the `max_width` the same way Excalidraw wraps the labels of the shapes:
+/// the words are moved to the next line, the words wider than the line are broken by characters.
+pub fn wrap_text(text: &str, max_width: i32, font_size: i32, font_family: i32) -> String {
+    let width = |text: &str| measure_text(text, font_size, font_family).0;
+    let mut lines = vec![];
+    for original_line in text.split('\n') {
+        let mut line = String::new();
+        for word in original_line.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if width(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+            line = String::new();
+            for c in word.chars() {
+                if !line.is_empty() && width(&format!("{}{}", line, c)) > max_width {
+                    lines.push(line);
+                    line = String::new();
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Advance width of the character in 1/1000 of the font size
+fn advance(c: char, font_family: i32) -> u16 {
+    let em = 1000;
+    match c {
+        '\t' => 4 * advance(' ', font_family),
+        _ if is_zero_width(c) => 0,
+        // full width characters take the whole em (two cells for the monospaced font)
+        _ if is_wide(c) => match font_family {
+            FONT_FAMILY_CASCADIA => 2 * CASCADIA_ADVANCE,
+            _ => em,
+        },
+        _ if font_family == FONT_FAMILY_CASCADIA => CASCADIA_ADVANCE,
+        ' '..='~' => {
+            let table = match font_family {
+                FONT_FAMILY_HELVETICA => &HELVETICA_ADVANCES,
+                _ => &VIRGIL_ADVANCES,
+            };
+            table[c as usize - ' ' as usize]
+        }
+        // letters of the other scripts are measured as the latin ones of the same case
+        _ if c.is_uppercase() => advance('H', font_family),
+        _ => advance('o', font_family),
+    }
+}
+
+fn is_zero_width(c: char) -> bool {
+    c.is_control()
+        || matches!(c as u32,
+            0x0300..=0x036F // combining diacritical marks
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x200B..=0x200F // zero width spaces and joiners
+            | 0x20D0..=0x20FF
+            | 0x2060
+            | 0xFE00..=0xFE0F // variation selectors
+            | 0xFE20..=0xFE2F
+            | 0xFEFF)
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F // hangul jamo
+        | 0x2E80..=0xA4CF // CJK
+        | 0xAC00..=0xD7A3 // hangul syllables
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60 // fullwidth forms
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F // emoji
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD)
+}
+
+fn div_ceil(value: i64, divisor: i64) -> i64 {
+    (value + divisor - 1) / divisor
+}
+
+#[test]
+fn test_measure_text() {
+    // monospaced: every character has the same advance
+    assert_eq!((36, 24), measure_text("abc", 20, FONT_FAMILY_CASCADIA));
+    assert_eq!(
+        measure_text("iii", 20, FONT_FAMILY_CASCADIA),
+        measure_text("WWW", 20, FONT_FAMILY_CASCADIA)
+    );
+    // proportional: narrow and wide letters differ
+    assert!(
+        measure_text("iii", 20, FONT_FAMILY_HELVETICA).0
+            < measure_text("WWW", 20, FONT_FAMILY_HELVETICA).0
+    );
+    // any font size scales the measurement, the widest line defines the width
+    let (width, height) = measure_text("redis\npostgres", 18, FONT_FAMILY_VIRGIL);
+    assert_eq!(measure_text("postgres", 18, FONT_FAMILY_VIRGIL).0, width);
+    assert_eq!(45, height);
+    assert!(width > measure_text("postgres", 13, FONT_FAMILY_VIRGIL).0);
+    // unicode text is measured by characters, not bytes
+    assert_eq!(
+        measure_text("cafe", 16, FONT_FAMILY_HELVETICA),
+        measure_text("cafe\u{301}", 16, FONT_FAMILY_HELVETICA)
+    );
+    assert_eq!((32, 19), measure_text("日本", 16, FONT_FAMILY_HELVETICA));
+    assert_eq!(
+        measure_text("Ä", 16, FONT_FAMILY_VIRGIL),
+        measure_text("H", 16, FONT_FAMILY_VIRGIL)
+    );
+}
+
+#[test]
+fn test_wrap_text() {
+    // 12px per character
+    let wrap = |text: &str, max_width: i32| wrap_text(text, max_width, 20, FONT_FAMILY_CASCADIA);
+    assert_eq!("redis", wrap("redis", 100));
+    assert_eq!("message\nbroker", wrap("message broker", 100));
+    assert_eq!("a b\nc", wrap("a b c", 40));
+    assert_eq!("postgre\nsql", wrap("postgresql", 90));
+    assert_eq!("kafka\nzookee\nper", wrap("kafka\nzookeeper", 72));
+}
@@ -1,13 +1,21 @@
+use std::collections::HashMap;
 use std::io::Read;
-use std::{fs::File, process::exit};
+use std::{env, fs, fs::File, process::exit};
 
+use indexmap::IndexMap;
 use isahc::ReadResponseExt;
 
 use serde_yaml::{Mapping, Value};
 
+use crate::exporters::excalidraw::ExcalidrawFile;
 use crate::exporters::excalidraw_config::DEFAULT_CONFIG;
+use crate::layout::{self, LayoutFile, Pin};
+use crate::{compose_utils, env_utils};
 use crate::{
-    error::ExcalidockerError::{self, FileIncorrectExtension, FileNotFound, RemoteFileFailedRead},
+    error::ExcalidockerError::{
+        self, FileFailedWrite, FileIncorrectExtension, FileNotFound, InvalidDockerCompose,
+        InvalidExcalidrawFile, RemoteFileFailedRead, RequiredVariableMissing,
+    },
     exporters::excalidraw_config::ExcalidrawConfig,
 };
 
@@ -30,27 +38,175 @@ pub fn get_excalidraw_config(file_path: &str) -> ExcalidrawConfig {
     }
 }
 
-pub fn get_docker_compose_content(file_path: &str) -> Mapping {
-    let file_content = match get_file_content(file_path) {
-        Ok(content) => content,
-        Err(err) => {
+/// Services pinned by the layout file. Only the explicitly provided layout file is required,
+/// the one next to the docker-compose file is optional
+pub fn get_layout_pins(file_path: &str, required: bool) -> IndexMap<String, Pin> {
+    let layout_contents = match read_yaml_file(file_path) {
+        Ok(layout_contents) => layout_contents,
+        Err(err) if required => {
             println!("{}", err);
-            return Mapping::new();
+            exit(1);
         }
+        Err(_) => return IndexMap::new(),
+    };
+    match serde_yaml::from_str::<LayoutFile>(&layout_contents) {
+        Ok(layout) => layout.services,
+        Err(err) => {
+            println!("Layout file parsing issue: {}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Writes the positions of the services drawn in the excalidraw file into the layout file.
+/// Returns the number of the extracted services
+pub fn extract_layout(
+    excalidraw_path: &str,
+    layout_path: &str,
+) -> Result<usize, ExcalidockerError> {
+    let excalidraw_file = read_excalidraw_file(excalidraw_path)?;
+    let layout = LayoutFile {
+        services: layout::extract_pins(&excalidraw_file.element_values()),
     };
-    return match serde_yaml::from_str::<Value>(&file_content) {
-        Ok(mut yaml_content) => {
-            let _ = yaml_content.apply_merge(); // TODO potentially here we know which files are using anchors
-            yaml_content
-                .as_mapping()
-                .unwrap_or(&Mapping::new())
-                .to_owned()
+    let layout_contents = serde_yaml::to_string(&layout).map_err(|err| FileFailedWrite {
+        path: layout_path.to_string(),
+        msg: err.to_string(),
+    })?;
+    fs::write(layout_path, layout_contents).map_err(|err| FileFailedWrite {
+        path: layout_path.to_string(),
+        msg: err.to_string(),
+    })?;
+    Ok(layout.services.len())
+}
+
+/// Read the existing excalidraw file
+pub fn read_excalidraw_file(file_path: &str) -> Result<ExcalidrawFile, ExcalidockerError> {
+    let excalidraw_contents = read_file(file_path)?;
+    serde_json::from_str(&excalidraw_contents).map_err(|err| InvalidExcalidrawFile {
+        path: file_path.to_string(),
+        msg: err.to_string(),
+    })
+}
+
+/// Variables used for the docker-compose interpolation.
+/// Env files provided via `--env-file` replace the `.env` file located next to the docker-compose file.
+/// The process environment takes precedence over the env files.
+pub fn get_interpolation_variables(
+    compose_file_path: &str,
+    env_files: &[String],
+) -> HashMap<String, String> {
+    let mut variables = HashMap::new();
+    let env_file_contents = if env_files.is_empty() {
+        if is_remote(compose_file_path) {
+            vec![]
+        } else {
+            // the `.env` file is optional
+            let dot_env_path = resolve_relative_path(compose_file_path, ".env");
+            read_file(&dot_env_path).into_iter().collect()
+        }
+    } else {
+        env_files
+            .iter()
+            .map(|env_file| match get_env_file_content(env_file) {
+                Ok(content) => content,
+                Err(err) => {
+                    println!("{}", err);
+                    exit(1);
+                }
+            })
+            .collect()
+    };
+    for content in env_file_contents {
+        for (key, value) in env_utils::parse_env_file_content(&content) {
+            if let Some(value) = value {
+                variables.insert(key, value);
+            }
+        }
+    }
+    variables.extend(
+        env::vars_os()
+            .filter_map(|(key, value)| key.into_string().ok().zip(value.into_string().ok())),
+    );
+    variables
+}
+
+pub fn get_docker_compose_content(file_path: &str, variables: &HashMap<String, String>) -> Mapping {
+    let content = match read_docker_compose(file_path, variables) {
+        Ok(content) => content,
+        Err(err @ RequiredVariableMissing { .. }) => {
+            println!("{}", err);
+            exit(1);
         }
         Err(err) => {
             println!("{}", err);
             return Mapping::new();
         }
     };
+    match compose_utils::resolve_include_and_extends(content, file_path, variables) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Read the docker-compose file, `<<` merge keys and variable interpolation are applied.
+pub fn read_docker_compose(
+    file_path: &str,
+    variables: &HashMap<String, String>,
+) -> Result<Mapping, ExcalidockerError> {
+    let file_content = get_file_content(file_path)?;
+    let mut yaml_content =
+        serde_yaml::from_str::<Value>(&file_content).map_err(|err| InvalidDockerCompose {
+            path: file_path.to_string(),
+            msg: err.to_string(),
+        })?;
+    let _ = yaml_content.apply_merge(); // TODO potentially here we know which files are using anchors
+    env_utils::interpolate_value(&mut yaml_content, variables)?;
+    Ok(yaml_content
+        .as_mapping()
+        .unwrap_or(&Mapping::new())
+        .to_owned())
+}
+
+/// Read the env file content (`env_file` attribute of a service).
+/// Both remote and local files are supported
+pub fn get_env_file_content(file_path: &str) -> Result<String, ExcalidockerError> {
+    if is_remote(file_path) {
+        get_remote_file_content(file_path)
+    } else {
+        read_file(file_path)
+    }
+}
+
+/// Resolves `relative_path` against the directory of `base_file_path`.
+/// Absolute paths and links are returned as is.
+pub fn resolve_relative_path(base_file_path: &str, relative_path: &str) -> String {
+    if relative_path.starts_with('/') || is_remote(relative_path) {
+        return relative_path.to_string();
+    }
+    match base_file_path.rfind('/') {
+        Some(index) => format!(
+            "{}/{}",
+            &base_file_path[..index],
+            relative_path.trim_start_matches("./")
+        ),
+        None => relative_path.to_string(),
+    }
+}
+
+/// Layout file next to the docker-compose file: `docker-compose.layout.yaml` for `docker-compose.yaml`.
+/// Remote docker-compose files don't have one
+pub fn layout_sidecar_path(compose_file_path: &str) -> Option<String> {
+    if is_remote(compose_file_path) {
+        return None;
+    }
+    let stem = compose_file_path
+        .strip_suffix(".yaml")
+        .or_else(|| compose_file_path.strip_suffix(".yml"))
+        .unwrap_or(compose_file_path);
+    Some(format!("{}.layout.yaml", stem))
 }
 
 /// Read yaml file content into a String
@@ -60,6 +216,11 @@ fn read_yaml_file(file_path: &str) -> Result<String, ExcalidockerError> {
             path: file_path.to_string(),
         });
     }
+    read_file(file_path)
+}
+
+/// Read file content into a String
+fn read_file(file_path: &str) -> Result<String, ExcalidockerError> {
     let mut file = match File::open(file_path) {
         Ok(file) => file,
         Err(err) => {
@@ -82,26 +243,41 @@ fn read_yaml_file(file_path: &str) -> Result<String, ExcalidockerError> {
 /// Get file content as a String.
 /// Both remote (f.e. from Github) and local files are supported
 fn get_file_content(file_path: &str) -> Result<String, ExcalidockerError> {
-    if file_path.starts_with("http") {
-        let url = rewrite_github_url(file_path);
-        let mut response = match isahc::get(url) {
-            Ok(rs) => rs,
-            Err(err) => {
-                return Err(RemoteFileFailedRead {
-                    path: file_path.to_string(),
-                    msg: err.to_string(),
-                })
-            }
-        };
-        match response.text() {
-            Ok(data) => Ok(data),
-            Err(err) => Err(RemoteFileFailedRead {
+    if is_remote(file_path) {
+        get_remote_file_content(file_path)
+    } else {
+        read_yaml_file(file_path)
+    }
+}
+
+/// Links are downloaded, everything else is read from the disk
+fn is_remote(file_path: &str) -> bool {
+    file_path.starts_with("http://") || file_path.starts_with("https://")
+}
+
+fn get_remote_file_content(file_path: &str) -> Result<String, ExcalidockerError> {
+    let url = rewrite_github_url(file_path);
+    let mut response = match isahc::get(url) {
+        Ok(rs) => rs,
+        Err(err) => {
+            return Err(RemoteFileFailedRead {
                 path: file_path.to_string(),
                 msg: err.to_string(),
-            }),
+            })
         }
-    } else {
-        read_yaml_file(file_path)
+    };
+    if !response.status().is_success() {
+        return Err(RemoteFileFailedRead {
+            path: file_path.to_string(),
+            msg: response.status().to_string(),
+        });
+    }
+    match response.text() {
+        Ok(data) => Ok(data),
+        Err(err) => Err(RemoteFileFailedRead {
+            path: file_path.to_string(),
+            msg: err.to_string(),
+        }),
     }
 }
 
@@ -141,3 +317,30 @@ fn test_rewrite_github_url() {
         rewrite_github_url(input4)
     );
 }
+
+#[test]
+fn test_resolve_relative_path() {
+    assert_eq!(
+        "./data/compose/.env",
+        resolve_relative_path("./data/compose/docker-compose.yaml", ".env")
+    );
+    assert_eq!(
+        "/tmp/env/app.env",
+        resolve_relative_path("/tmp/docker-compose.yaml", "./env/app.env")
+    );
+    assert_eq!(
+        "/etc/app.env",
+        resolve_relative_path("/tmp/docker-compose.yaml", "/etc/app.env")
+    );
+    assert_eq!(
+        "app.env",
+        resolve_relative_path("docker-compose.yaml", "app.env")
+    );
+    assert_eq!(
+        "https://github.com/etolbakov/excalidocker-rs/blob/main/data/compose/app.env",
+        resolve_relative_path(
+            "https://github.com/etolbakov/excalidocker-rs/blob/main/data/compose/docker-compose.yaml",
+            "app.env"
+        )
+    );
+}
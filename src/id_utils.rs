@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+const ID_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const ID_LENGTH: usize = 7;
+
+/// Generates the ids of the elements from the names of the things they depict
+/// (services, ports, networks...), so the same input always gives the same ids.
+/// The `seed` allows getting the different set of ids for the same input.
+pub struct IdGenerator {
+    seed: u64,
+    used: HashSet<String>,
+}
+
+impl IdGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            used: HashSet::new(),
+        }
+    }
+
+    /// `<prefix>_<hash of the parts>`, the hash is recalculated in the unlikely case of collision
+    pub fn id(&mut self, prefix: &str, parts: &[&str]) -> String {
+        let mut hash = fnv1a(self.seed, parts);
+        loop {
+            let id = format!("{}_{}", prefix, encode(hash));
+            if self.used.insert(id.clone()) {
+                return id;
+            }
+            hash = fnv1a(hash, parts);
+        }
+    }
+}
+
+/// Seed of the strokes drawn by Excalidraw for the element with the given key (id or text).
+/// Excalidraw expects a positive 31 bit integer
+pub fn element_seed(seed: u64, key: &str) -> i32 {
+    (fnv1a(seed, &[key]) % (i32::MAX as u64)) as i32 + 1
+}
+
+/// FNV-1a hash of the parts, stable across the platforms and Rust versions
+/// unlike the `DefaultHasher`
+fn fnv1a(seed: u64, parts: &[&str]) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64 ^ seed;
+    for part in parts {
+        // the separator keeps ["ab", "c"] and ["a", "bc"] apart
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn encode(mut hash: u64) -> String {
+    (0..ID_LENGTH)
+        .map(|_| {
+            let c = ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize];
+            hash /= ID_ALPHABET.len() as u64;
+            c as char
+        })
+        .collect()
+}
+
+#[test]
+fn test_generate_id() {
+    let mut ids = IdGenerator::new(0);
+    let id = ids.id("ellipse", &["backend", "8080:80"]);
+    assert!(id.starts_with("ellipse_"));
+    assert_eq!(
+        id,
+        IdGenerator::new(0).id("ellipse", &["backend", "8080:80"])
+    );
+    assert_ne!(
+        id,
+        IdGenerator::new(1).id("ellipse", &["backend", "8080:80"])
+    );
+    assert_ne!(id, IdGenerator::new(0).id("ellipse", &["backend8080:80"]));
+    // the same parts requested twice still give the unique ids
+    assert_ne!(id, ids.id("ellipse", &["backend", "8080:80"]));
+
+    assert_eq!(element_seed(0, "backend"), element_seed(0, "backend"));
+    assert_ne!(element_seed(0, "backend"), element_seed(7, "backend"));
+    assert!(element_seed(0, "backend") > 0);
+}
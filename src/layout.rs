@@ -0,0 +1,995 @@
+//! Layouts which place all the services at once, as opposed to the step by step
+//! alignment (`stepped`, `vertical`, `horizontal`) performed while the services are rendered.
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+/// Footprint of a service on the canvas: the card along with its ports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutNode {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl LayoutNode {
+    pub fn new(name: &str, width: i32, height: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            width,
+            height,
+        }
+    }
+}
+
+/// Top left corner of each service
+pub type Positions = IndexMap<String, (i32, i32)>;
+
+/// Block containing the services of the same group, the `label` is rendered at the top of the block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupFrame {
+    pub label: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Lays out each group as a block and arranges the blocks in rows (shelves) without overlaps.
+/// `groups` hold the names of the nodes in the order they should be placed,
+/// `label_height` is reserved at the top of each block.
+pub fn grouped(
+    nodes: &[LayoutNode],
+    groups: &[(Option<String>, Vec<String>)],
+    gap: i32,
+    label_height: i32,
+) -> (Positions, Vec<GroupFrame>) {
+    let blocks = groups
+        .iter()
+        .map(|(label, names)| {
+            let members = names
+                .iter()
+                .filter_map(|name| nodes.iter().find(|node| &node.name == name))
+                .cloned()
+                .collect::<Vec<LayoutNode>>();
+            let columns = (members.len() as f64).sqrt().ceil() as usize;
+            let (positions, width, height) = grid_block(&members, columns, gap, gap);
+            (
+                label,
+                positions,
+                width + 2 * gap,
+                height + 2 * gap + label_height,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // the blocks are arranged in the rows of roughly the same width
+    let total_area: i64 = blocks
+        .iter()
+        .map(|(_, _, width, height)| *width as i64 * *height as i64)
+        .sum();
+    let widest_block = blocks.iter().map(|(_, _, width, _)| *width).max();
+    let row_width = widest_block
+        .unwrap_or(0)
+        .max(((total_area as f64).sqrt() * 1.5) as i32);
+
+    let mut positions = Positions::new();
+    let mut frames = vec![];
+    let (mut block_x, mut block_y, mut row_height) = (0, 0, 0);
+    for (label, block_positions, width, height) in blocks {
+        if block_x > 0 && block_x + width > row_width {
+            block_x = 0;
+            block_y += row_height + gap;
+            row_height = 0;
+        }
+        for (name, (x, y)) in block_positions {
+            positions.insert(name, (block_x + gap + x, block_y + gap + label_height + y));
+        }
+        frames.push(GroupFrame {
+            label: label.clone(),
+            x: block_x,
+            y: block_y,
+            width,
+            height,
+        });
+        block_x += width + gap;
+        row_height = row_height.max(height);
+    }
+    (positions, frames)
+}
+
+/// Places the nodes in a grid of the given number of `columns` row by row.
+/// Each column is as wide as its widest node and each row is as high as its highest node.
+/// Returns the positions relative to the top left corner of the grid along with the grid size.
+pub fn grid_block(
+    nodes: &[LayoutNode],
+    columns: usize,
+    column_gap: i32,
+    row_gap: i32,
+) -> (Positions, i32, i32) {
+    let columns = columns.max(1);
+    let rows = nodes.len().div_ceil(columns);
+    let mut column_widths = vec![0; columns];
+    let mut row_heights = vec![0; rows];
+    for (i, node) in nodes.iter().enumerate() {
+        column_widths[i % columns] = column_widths[i % columns].max(node.width);
+        row_heights[i / columns] = row_heights[i / columns].max(node.height);
+    }
+    let offsets = |sizes: &[i32], gap: i32| {
+        sizes
+            .iter()
+            .scan(0, |offset, size| {
+                let current = *offset;
+                *offset += size + gap;
+                Some(current)
+            })
+            .collect::<Vec<i32>>()
+    };
+    let column_offsets = offsets(&column_widths, column_gap);
+    let row_offsets = offsets(&row_heights, row_gap);
+    let positions = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            (
+                node.name.clone(),
+                (column_offsets[i % columns], row_offsets[i / columns]),
+            )
+        })
+        .collect();
+    let total =
+        |sizes: &[i32], gap: i32| sizes.iter().sum::<i32>() + gap * (sizes.len() as i32 - 1).max(0);
+    (
+        positions,
+        total(&column_widths, column_gap),
+        total(&row_heights, row_gap),
+    )
+}
+
+/// Number of the barycenter sweeps (down and up) performed to reduce the edge crossings
+const CROSSING_REDUCTION_SWEEPS: usize = 12;
+
+/// Layered (Sugiyama-style) layout of the dependency graph.
+/// `nodes` are expected in the traversal order (parents first), `edges` are `(child, parent)` pairs.
+/// Each node is ranked one level below its deepest parent (the highest parent rank plus one);
+/// the edges to the nodes which come later in the traversal order (cycles) are ignored for ranking.
+/// Long edges are split with virtual nodes, then each rank starting in the preferred `order`
+/// (f.e. the members of the same network next to each other) is reordered with the barycenter heuristic
+/// to reduce the edge crossings.
+/// The ranks follow the `direction`: `top-down` (parents on top), `bottom-up`, `left-right` or `right-left`.
+pub fn layered(
+    nodes: &[LayoutNode],
+    edges: &[(String, String)],
+    order: &[String],
+    direction: &str,
+    gap: i32,
+) -> Positions {
+    let index_of = |name: &str| nodes.iter().position(|node| node.name == name);
+    let edges = edges
+        .iter()
+        .filter_map(|(child, parent)| Some((index_of(child)?, index_of(parent)?)))
+        .filter(|(child, parent)| parent < child)
+        .collect::<Vec<(usize, usize)>>();
+
+    // ------------ rank assignment ------------
+    let mut ranks = vec![0; nodes.len()];
+    for child in 0..nodes.len() {
+        ranks[child] = edges
+            .iter()
+            .filter(|(c, _)| *c == child)
+            .map(|(_, parent)| ranks[*parent] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+
+    // ------------ virtual nodes for the edges spanning several ranks ------------
+    // `layers` hold the node indexes, the virtual nodes are indexed after the real ones
+    let rank_count = ranks.iter().max().map_or(0, |max| max + 1);
+    let mut layers: Vec<Vec<usize>> = vec![vec![]; rank_count];
+    for (node, rank) in ranks.iter().enumerate() {
+        layers[*rank].push(node);
+    }
+    let order_of = |node: &usize| {
+        order
+            .iter()
+            .position(|name| *name == nodes[*node].name)
+            .unwrap_or(usize::MAX)
+    };
+    for layer in &mut layers {
+        layer.sort_by_key(order_of);
+    }
+    let mut segments: Vec<(usize, usize)> = vec![];
+    let mut node_count = nodes.len();
+    for (child, parent) in &edges {
+        let mut upper = *parent;
+        for layer in &mut layers[ranks[*parent] + 1..ranks[*child]] {
+            layer.push(node_count);
+            segments.push((upper, node_count));
+            upper = node_count;
+            node_count += 1;
+        }
+        segments.push((upper, *child));
+    }
+
+    // ------------ crossing reduction ------------
+    let mut best_layers = layers.clone();
+    let mut best_crossings = count_crossings(&layers, &segments);
+    for sweep in 0..CROSSING_REDUCTION_SWEEPS {
+        let downwards = sweep % 2 == 0;
+        let rank_order: Vec<usize> = if downwards {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for rank in rank_order {
+            let fixed = if downwards {
+                &layers[rank - 1]
+            } else {
+                &layers[rank + 1]
+            };
+            let neighbour_positions = |node: usize| {
+                segments
+                    .iter()
+                    .filter_map(|(upper, lower)| match downwards {
+                        true if *lower == node => Some(*upper),
+                        false if *upper == node => Some(*lower),
+                        _ => None,
+                    })
+                    .filter_map(|neighbour| fixed.iter().position(|n| *n == neighbour))
+                    .collect::<Vec<usize>>()
+            };
+            let mut barycenters = layers[rank]
+                .iter()
+                .enumerate()
+                .map(|(position, node)| {
+                    let positions = neighbour_positions(*node);
+                    // nodes without neighbours keep their position
+                    let barycenter = if positions.is_empty() {
+                        position as f64
+                    } else {
+                        positions.iter().sum::<usize>() as f64 / positions.len() as f64
+                    };
+                    (barycenter, *node)
+                })
+                .collect::<Vec<(f64, usize)>>();
+            barycenters.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[rank] = barycenters.into_iter().map(|(_, node)| node).collect();
+        }
+        let crossings = count_crossings(&layers, &segments);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best_layers = layers.clone();
+        }
+    }
+
+    // ------------ coordinates ------------
+    // `along` is the coordinate within a rank, `across` is the coordinate of the rank itself
+    let horizontal = matches!(direction, "left-right" | "right-left");
+    let extent = |node: usize| match nodes.get(node) {
+        Some(node) if horizontal => (node.height, node.width),
+        Some(node) => (node.width, node.height),
+        // virtual nodes only keep some space for the edges passing through
+        None => (gap, 0),
+    };
+    let rank_lengths = best_layers
+        .iter()
+        .map(|layer| {
+            layer.iter().map(|node| extent(*node).0).sum::<i32>()
+                + gap * (layer.len() as i32 - 1).max(0)
+        })
+        .collect::<Vec<i32>>();
+    let longest_rank = rank_lengths.iter().copied().max().unwrap_or(0);
+    let rank_gap = 2 * gap;
+    let mut positions = Positions::new();
+    let mut across = 0;
+    let mut placed = vec![];
+    for (layer, rank_length) in best_layers.iter().zip(rank_lengths) {
+        // ranks are centered against the longest one
+        let mut along = (longest_rank - rank_length) / 2;
+        let rank_thickness = layer.iter().map(|node| extent(*node).1).max().unwrap_or(0);
+        for node in layer {
+            let (node_along, node_across) = extent(*node);
+            if *node < nodes.len() {
+                placed.push((*node, along, across, node_across));
+            }
+            along += node_along + gap;
+        }
+        across += rank_thickness + rank_gap;
+    }
+    let total_across = (across - rank_gap).max(0);
+    for (node, along, across, node_across) in placed {
+        let position = match direction {
+            "bottom-up" => (along, total_across - across - node_across),
+            "left-right" => (across, along),
+            "right-left" => (total_across - across - node_across, along),
+            _ => (along, across),
+        };
+        positions.insert(nodes[node].name.clone(), position);
+    }
+    // the nodes are listed in the original order
+    nodes
+        .iter()
+        .filter_map(|node| {
+            positions
+                .get(&node.name)
+                .map(|position| (node.name.clone(), *position))
+        })
+        .collect()
+}
+
+/// Pull of every node towards the centre, keeps the disconnected services close
+const GRAVITY: f64 = 0.3;
+/// Limits the passes pushing the overlapping nodes apart
+const OVERLAP_REMOVAL_PASSES: usize = 500;
+
+/// Force-directed (Fruchterman-Reingold) layout for the densely connected services.
+/// `edges` are `(child, parent)` pairs, each of the `hubs` (f.e. a network or a volume) is simulated
+/// as a virtual node attracting its members. The simulation is deterministic for the given `seed`.
+/// Overlaps between the node footprints are removed afterwards keeping at least the `gap` between them.
+pub fn force(
+    nodes: &[LayoutNode],
+    edges: &[(String, String)],
+    hubs: &[Vec<String>],
+    seed: u64,
+    iterations: usize,
+    gap: i32,
+) -> Positions {
+    if nodes.is_empty() {
+        return Positions::new();
+    }
+    let index_of = |name: &str| nodes.iter().position(|node| node.name == name);
+    // hubs are indexed after the real nodes
+    let mut springs = edges
+        .iter()
+        .filter_map(|(child, parent)| Some((index_of(child)?, index_of(parent)?)))
+        .filter(|(child, parent)| child != parent)
+        .collect::<Vec<(usize, usize)>>();
+    let mut node_count = nodes.len();
+    for members in hubs.iter().filter(|members| members.len() > 1) {
+        for member in members.iter().filter_map(|member| index_of(member)) {
+            springs.push((member, node_count));
+        }
+        node_count += 1;
+    }
+
+    // the ideal distance between the connected nodes
+    let average_diagonal = nodes
+        .iter()
+        .map(|node| ((node.width.pow(2) + node.height.pow(2)) as f64).sqrt())
+        .sum::<f64>()
+        / nodes.len() as f64;
+    let k = average_diagonal + 2.0 * gap as f64;
+    let side = (node_count as f64).sqrt() * k;
+
+    let mut random = SplitMix64(seed);
+    let mut centers = (0..node_count)
+        .map(|_| (random.next_f64() * side, random.next_f64() * side))
+        .collect::<Vec<(f64, f64)>>();
+    let iterations = iterations.max(1);
+    for iteration in 0..iterations {
+        // the temperature limits the displacement and cools down linearly
+        let temperature = side / 10.0 * (1.0 - iteration as f64 / iterations as f64) + 1.0;
+        let mut displacements = vec![(0.0, 0.0); node_count];
+        for i in 0..node_count {
+            for j in i + 1..node_count {
+                let (dx, dy, distance) = delta(centers[i], centers[j], i, j);
+                let repulsion = k * k / distance;
+                displacements[i].0 += dx / distance * repulsion;
+                displacements[i].1 += dy / distance * repulsion;
+                displacements[j].0 -= dx / distance * repulsion;
+                displacements[j].1 -= dy / distance * repulsion;
+            }
+        }
+        for (a, b) in &springs {
+            let (dx, dy, distance) = delta(centers[*a], centers[*b], *a, *b);
+            let attraction = distance * distance / k;
+            displacements[*a].0 -= dx / distance * attraction;
+            displacements[*a].1 -= dy / distance * attraction;
+            displacements[*b].0 += dx / distance * attraction;
+            displacements[*b].1 += dy / distance * attraction;
+        }
+        // gravity keeps the disconnected nodes close to the rest of the diagram
+        for (center, displacement) in centers.iter().zip(displacements.iter_mut()) {
+            let (dx, dy) = (center.0 - side / 2.0, center.1 - side / 2.0);
+            let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+            let gravity = GRAVITY * distance * distance / k;
+            displacement.0 -= dx / distance * gravity;
+            displacement.1 -= dy / distance * gravity;
+        }
+        for (center, (dx, dy)) in centers.iter_mut().zip(displacements) {
+            let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+            let limited = length.min(temperature);
+            center.0 += dx / length * limited;
+            center.1 += dy / length * limited;
+        }
+    }
+
+    // ------------ overlap removal ------------
+    let mut boxes = nodes
+        .iter()
+        .zip(&centers)
+        .map(|(node, (x, y))| {
+            (
+                x - node.width as f64 / 2.0,
+                y - node.height as f64 / 2.0,
+                node.width as f64 + gap as f64,
+                node.height as f64 + gap as f64,
+            )
+        })
+        .collect::<Vec<(f64, f64, f64, f64)>>();
+    for _ in 0..OVERLAP_REMOVAL_PASSES {
+        let mut moved = false;
+        for i in 0..boxes.len() {
+            for j in i + 1..boxes.len() {
+                let (a, b) = (boxes[i], boxes[j]);
+                let overlap_x = (a.0 + a.2).min(b.0 + b.2) - a.0.max(b.0);
+                let overlap_y = (a.1 + a.3).min(b.1 + b.3) - a.1.max(b.1);
+                if overlap_x <= 0.0 || overlap_y <= 0.0 {
+                    continue;
+                }
+                moved = true;
+                // the boxes are pushed apart along the axis of the smaller overlap
+                if overlap_x < overlap_y {
+                    let shift = if a.0 <= b.0 { overlap_x } else { -overlap_x } / 2.0;
+                    boxes[i].0 -= shift;
+                    boxes[j].0 += shift;
+                } else {
+                    let shift = if a.1 <= b.1 { overlap_y } else { -overlap_y } / 2.0;
+                    boxes[i].1 -= shift;
+                    boxes[j].1 += shift;
+                }
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    // the diagram starts at the origin
+    let min_x = boxes.iter().map(|b| b.0).fold(f64::MAX, f64::min);
+    let min_y = boxes.iter().map(|b| b.1).fold(f64::MAX, f64::min);
+    nodes
+        .iter()
+        .zip(boxes)
+        .map(|(node, (x, y, _, _))| {
+            (
+                node.name.clone(),
+                ((x - min_x).ceil() as i32, (y - min_y).ceil() as i32),
+            )
+        })
+        .collect()
+}
+
+/// Position of a service pinned by the layout file:
+/// either fixed coordinates or an offset from another service (`anchor`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Pin {
+    Relative {
+        anchor: String,
+        #[serde(default)]
+        dx: i32,
+        #[serde(default)]
+        dy: i32,
+    },
+    Fixed {
+        x: i32,
+        y: i32,
+    },
+}
+
+/// Content of the layout file (`docker-compose.layout.yaml` next to `docker-compose.yaml`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LayoutFile {
+    #[serde(default)]
+    pub services: IndexMap<String, Pin>,
+}
+
+/// Moves the pinned nodes to their positions, the other nodes are moved out of the way
+/// of the pinned ones as little as possible (keeping `gap` between the footprints).
+/// Services pinned relative to an unpinned service follow it.
+/// Returns the names of the pins which could not be resolved (unknown anchor or a cycle).
+pub fn pin(
+    positions: &mut Positions,
+    nodes: &[LayoutNode],
+    pins: &IndexMap<String, Pin>,
+    gap: i32,
+) -> Vec<String> {
+    // the pins which can't be resolved are ignored, so those nodes are placed as the unpinned ones
+    let unresolved = nodes
+        .iter()
+        .filter(|node| pins.contains_key(&node.name))
+        .filter(|node| {
+            let layout_position = |name: &str| {
+                (!pins.contains_key(name))
+                    .then(|| positions.get(name).copied())
+                    .flatten()
+            };
+            resolve_pin(&node.name, pins, &layout_position, &mut vec![]).is_none()
+        })
+        .map(|node| node.name.clone())
+        .collect::<Vec<String>>();
+    let pins = pins
+        .iter()
+        .filter(|(name, _)| !unresolved.contains(name))
+        .map(|(name, pin)| (name.clone(), pin.clone()))
+        .collect::<IndexMap<String, Pin>>();
+    let pinned_nodes = nodes
+        .iter()
+        .filter(|node| pins.contains_key(&node.name))
+        .collect::<Vec<&LayoutNode>>();
+    if pinned_nodes.is_empty() {
+        return unresolved;
+    }
+    // pins which don't depend on the layout come first, the unpinned nodes are placed around them
+    let mut occupied = vec![];
+    let mut pending = vec![];
+    for node in pinned_nodes {
+        match resolve_pin(&node.name, &pins, &|_| None, &mut vec![]) {
+            Some(position) => {
+                positions.insert(node.name.clone(), position);
+                occupied.push((position.0, position.1, node.width, node.height));
+            }
+            None => pending.push(node),
+        }
+    }
+    // the anchors of the pins are placed first, so the pins following them are avoided as well
+    let is_anchor = |name: &str| {
+        pins.values()
+            .any(|pin| matches!(pin, Pin::Relative { anchor, .. } if anchor == name))
+    };
+    let (anchors, others): (Vec<&LayoutNode>, Vec<&LayoutNode>) = nodes
+        .iter()
+        .filter(|node| !pins.contains_key(&node.name))
+        .partition(|node| is_anchor(&node.name));
+    let unpinned_nodes = anchors
+        .into_iter()
+        .chain(others)
+        .collect::<Vec<&LayoutNode>>();
+    let mut placed = HashSet::new();
+    for node in &unpinned_nodes {
+        let Some(&start) = positions.get(&node.name) else {
+            continue;
+        };
+        // the other unpinned nodes are avoided at their current positions
+        let others = unpinned_nodes
+            .iter()
+            .filter(|other| other.name != node.name)
+            .filter_map(|other| {
+                let (x, y) = positions.get(&other.name)?;
+                Some((*x, *y, other.width, other.height))
+            });
+        let obstacles = occupied.iter().copied().chain(others).collect::<Vec<_>>();
+        let position = free_position(start, (node.width, node.height), &obstacles, gap);
+        positions.insert(node.name.clone(), position);
+        placed.insert(node.name.as_str());
+        // the pins whose anchors are placed take their positions right away
+        let layout_position = |name: &str| {
+            placed
+                .contains(name)
+                .then(|| positions.get(name).copied())
+                .flatten()
+        };
+        let (resolved, unresolved_yet): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .map(|pinned: &LayoutNode| {
+                let position = resolve_pin(&pinned.name, &pins, &layout_position, &mut vec![]);
+                (pinned, position)
+            })
+            .partition(|(_, position)| position.is_some());
+        pending = unresolved_yet
+            .into_iter()
+            .map(|(pinned, _)| pinned)
+            .collect();
+        for (pinned, position) in resolved {
+            let (x, y) = position.unwrap_or_default();
+            positions.insert(pinned.name.clone(), (x, y));
+            occupied.push((x, y, pinned.width, pinned.height));
+        }
+    }
+    // pins anchored to the nodes placed outside of the layout follow them
+    for node in &pending {
+        let layout_position = |name: &str| {
+            (!pins.contains_key(name))
+                .then(|| positions.get(name).copied())
+                .flatten()
+        };
+        if let Some(position) = resolve_pin(&node.name, &pins, &layout_position, &mut vec![]) {
+            positions.insert(node.name.clone(), position);
+        }
+    }
+    unresolved
+}
+
+/// Position of the pinned node, `layout_position` provides the positions of the unpinned ones.
+/// `visiting` holds the chain of the anchors to detect cycles.
+fn resolve_pin(
+    name: &str,
+    pins: &IndexMap<String, Pin>,
+    layout_position: &dyn Fn(&str) -> Option<(i32, i32)>,
+    visiting: &mut Vec<String>,
+) -> Option<(i32, i32)> {
+    match pins.get(name) {
+        Some(Pin::Fixed { x, y }) => Some((*x, *y)),
+        Some(Pin::Relative { anchor, dx, dy }) => {
+            if visiting.iter().any(|visited| visited == name) {
+                return None;
+            }
+            visiting.push(name.to_string());
+            let (x, y) = resolve_pin(anchor, pins, layout_position, visiting)?;
+            visiting.pop();
+            Some((x + dx, y + dy))
+        }
+        None => layout_position(name),
+    }
+}
+
+/// Limits the search of a free place for a node moved by the pinned ones
+const FREE_POSITION_CANDIDATES: usize = 1000;
+
+/// The nearest position to `start` where the node doesn't overlap any of the `occupied` rectangles.
+/// Candidates are found by pushing the node past the sides of the rectangles it overlaps.
+fn free_position(
+    start: (i32, i32),
+    (width, height): (i32, i32),
+    occupied: &[(i32, i32, i32, i32)],
+    gap: i32,
+) -> (i32, i32) {
+    let overlapping = |(x, y): (i32, i32)| {
+        occupied
+            .iter()
+            .filter(|(ox, oy, ow, oh)| {
+                x < ox + ow + gap
+                    && *ox < x + width + gap
+                    && y < oy + oh + gap
+                    && *oy < y + height + gap
+            })
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    let distance = |(x, y): (i32, i32)| (x - start.0).abs() + (y - start.1).abs();
+    let mut candidates = BinaryHeap::from([Reverse((0, start))]);
+    let mut visited = HashSet::new();
+    while let Some(Reverse((_, position))) = candidates.pop() {
+        if visited.len() > FREE_POSITION_CANDIDATES {
+            break;
+        }
+        if !visited.insert(position) {
+            continue;
+        }
+        let blocking = overlapping(position);
+        if blocking.is_empty() {
+            return position;
+        }
+        for (ox, oy, ow, oh) in blocking {
+            for candidate in [
+                (ox + ow + gap, position.1),
+                (ox - width - gap, position.1),
+                (position.0, oy + oh + gap),
+                (position.0, oy - height - gap),
+            ] {
+                candidates.push(Reverse((distance(candidate), candidate)));
+            }
+        }
+    }
+    // no free place nearby, the node goes below everything
+    let bottom = occupied.iter().map(|(_, y, _, h)| y + h).max();
+    (start.0, bottom.unwrap_or(start.1) + gap)
+}
+
+/// Positions of the services drawn in an Excalidraw file
+pub fn extract_pins(elements: &[Value]) -> IndexMap<String, Pin> {
+    extract_services(elements)
+        .into_iter()
+        .map(|(name, (x, y, _, _))| (name, Pin::Fixed { x, y }))
+        .collect()
+}
+
+/// Bounds (`x`, `y`, `width`, `height`) of the services drawn in an Excalidraw file.
+/// The service rectangles are found by the service name kept in `customData`,
+/// the drawings without it are matched by the `container_*` ids and named after
+/// the topmost text inside the rectangle.
+pub fn extract_services(elements: &[Value]) -> IndexMap<String, (i32, i32, i32, i32)> {
+    let live_elements = elements
+        .iter()
+        .filter(|element| !element["isDeleted"].as_bool().unwrap_or(false))
+        .collect::<Vec<&Value>>();
+    let bounds = |element: &Value| {
+        let number = |key: &str| element[key].as_f64().map(|value| value.round() as i32);
+        Some((
+            number("x")?,
+            number("y")?,
+            number("width")?,
+            number("height")?,
+        ))
+    };
+    let texts = live_elements
+        .iter()
+        .filter(|element| element["type"] == "text")
+        .filter_map(|element| Some((bounds(element)?, element["text"].as_str()?)))
+        .collect::<Vec<_>>();
+    live_elements
+        .iter()
+        .filter(|element| element["type"] == "rectangle")
+        .filter_map(|rectangle| {
+            let (x, y, width, height) = bounds(rectangle)?;
+            let name = match rectangle["customData"]["excalidocker"]["service"].as_str() {
+                Some(service) => service,
+                None if rectangle["id"]
+                    .as_str()
+                    .is_some_and(|id| id.starts_with("container_")) =>
+                {
+                    texts
+                        .iter()
+                        .filter(|((text_x, text_y, _, _), _)| {
+                            (x..x + width).contains(text_x) && (y..y + height).contains(text_y)
+                        })
+                        .min_by_key(|((_, text_y, _, _), _)| *text_y)
+                        .and_then(|(_, text)| text.lines().next())?
+                }
+                None => return None,
+            };
+            Some((name.to_string(), (x, y, width, height)))
+        })
+        .collect()
+}
+
+/// Vector between the two points along with its length,
+/// the coincident points are separated deterministically
+fn delta(a: (f64, f64), b: (f64, f64), i: usize, j: usize) -> (f64, f64, f64) {
+    let (mut dx, mut dy) = (a.0 - b.0, a.1 - b.1);
+    if dx.abs() < f64::EPSILON && dy.abs() < f64::EPSILON {
+        dx = (i as f64 - j as f64).signum();
+        dy = 1.0;
+    }
+    let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+    (dx, dy, distance)
+}
+
+/// Small deterministic pseudo random generator, thus the layout does not depend on the `rand` internals
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed value in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Number of the crossings between the segments connecting the adjacent layers
+fn count_crossings(layers: &[Vec<usize>], segments: &[(usize, usize)]) -> usize {
+    let position = |node: usize| {
+        layers
+            .iter()
+            .find_map(|layer| layer.iter().position(|n| *n == node))
+            .unwrap_or(0)
+    };
+    let positioned = segments
+        .iter()
+        .map(|(upper, lower)| (position(*upper), position(*lower), *upper))
+        .collect::<Vec<(usize, usize, usize)>>();
+    let rank_of = |node: usize| {
+        layers
+            .iter()
+            .position(|layer| layer.contains(&node))
+            .unwrap_or(0)
+    };
+    let mut crossings = 0;
+    for (i, (upper_a, lower_a, node_a)) in positioned.iter().enumerate() {
+        for (upper_b, lower_b, node_b) in positioned.iter().skip(i + 1) {
+            if rank_of(*node_a) == rank_of(*node_b)
+                && ((upper_a < upper_b && lower_a > lower_b)
+                    || (upper_a > upper_b && lower_a < lower_b))
+            {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+#[test]
+fn test_grouped_layout() {
+    let nodes = ["a", "b", "c", "d", "e"]
+        .iter()
+        .map(|name| LayoutNode::new(name, 100, 50))
+        .collect::<Vec<LayoutNode>>();
+    let groups = vec![
+        (
+            Some("backend".to_string()),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        ),
+        (None, vec!["d".to_string(), "e".to_string()]),
+    ];
+    let (positions, frames) = grouped(&nodes, &groups, 10, 20);
+    // 3 nodes are placed in 2 columns, 2 nodes in 2 columns
+    assert_eq!(positions["a"], (10, 30));
+    assert_eq!(positions["b"], (120, 30));
+    assert_eq!(positions["c"], (10, 90));
+    assert_eq!(
+        frames[0],
+        GroupFrame {
+            label: Some("backend".to_string()),
+            x: 0,
+            y: 0,
+            width: 230,
+            height: 150
+        }
+    );
+    // blocks don't overlap
+    let (first, second) = (&frames[0], &frames[1]);
+    assert!(
+        second.x >= first.x + first.width || second.y >= first.y + first.height,
+        "{:?} overlaps {:?}",
+        first,
+        second
+    );
+}
+
+#[test]
+fn test_layered_layout() {
+    let nodes = ["db", "cache", "api", "worker", "web"]
+        .iter()
+        .map(|name| LayoutNode::new(name, 100, 50))
+        .collect::<Vec<LayoutNode>>();
+    let edge = |child: &str, parent: &str| (child.to_string(), parent.to_string());
+    let edges = vec![
+        edge("api", "db"),
+        edge("api", "cache"),
+        edge("worker", "db"),
+        edge("web", "api"),
+        edge("web", "db"),
+    ];
+    let positions = layered(&nodes, &edges, &[], "top-down", 10);
+    // ranks: db, cache -> api, worker -> web
+    assert_eq!(positions["db"].1, 0);
+    assert_eq!(positions["cache"].1, 0);
+    assert_eq!(positions["api"].1, 70);
+    assert_eq!(positions["worker"].1, 70);
+    assert_eq!(positions["web"].1, 140);
+    // nodes of a rank don't overlap
+    assert!((positions["api"].0 - positions["worker"].0).abs() >= 100);
+
+    let positions = layered(&nodes, &edges, &[], "bottom-up", 10);
+    assert_eq!(positions["db"].1, 140);
+    assert_eq!(positions["web"].1, 0);
+
+    let positions = layered(&nodes, &edges, &[], "left-right", 10);
+    assert_eq!(positions["db"].0, 0);
+    assert_eq!(positions["api"].0, 120);
+
+    let positions = layered(&nodes, &edges, &[], "right-left", 10);
+    assert_eq!(positions["web"].0, 0);
+    assert_eq!(positions["db"].0, 240);
+
+    // the nodes of a rank without edges keep the preferred order
+    let order = ["worker", "web", "api", "cache", "db"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>();
+    let positions = layered(&nodes, &[], &order, "top-down", 10);
+    assert!(positions["worker"].0 < positions["web"].0);
+    assert!(positions["api"].0 < positions["cache"].0);
+    assert!(positions["cache"].0 < positions["db"].0);
+}
+
+#[test]
+fn test_count_crossings() {
+    // 0 1
+    //  X
+    // 2 3
+    let layers = vec![vec![0, 1], vec![2, 3]];
+    assert_eq!(count_crossings(&layers, &[(0, 3), (1, 2)]), 1);
+    assert_eq!(count_crossings(&layers, &[(0, 2), (1, 3)]), 0);
+}
+
+#[test]
+fn test_force_layout() {
+    let nodes = ["proxy", "app", "db", "cache", "worker", "queue"]
+        .iter()
+        .map(|name| LayoutNode::new(name, 140, 60 + name.len() as i32 * 10))
+        .collect::<Vec<LayoutNode>>();
+    let edge = |child: &str, parent: &str| (child.to_string(), parent.to_string());
+    let edges = vec![
+        edge("proxy", "app"),
+        edge("app", "db"),
+        edge("app", "cache"),
+        edge("worker", "queue"),
+        edge("worker", "db"),
+    ];
+    let hubs = vec![vec!["app".to_string(), "worker".to_string()]];
+    let positions = force(&nodes, &edges, &hubs, 42, 200, 20);
+    // the simulation is deterministic
+    assert_eq!(positions, force(&nodes, &edges, &hubs, 42, 200, 20));
+    assert_eq!(positions.len(), nodes.len());
+    // the footprints don't overlap
+    for (i, a) in nodes.iter().enumerate() {
+        for b in nodes.iter().skip(i + 1) {
+            let (ax, ay) = positions[&a.name];
+            let (bx, by) = positions[&b.name];
+            let overlaps =
+                ax < bx + b.width && bx < ax + a.width && ay < by + b.height && by < ay + a.height;
+            assert!(!overlaps, "{} overlaps {}", a.name, b.name);
+        }
+    }
+    assert_eq!(
+        0,
+        positions.values().map(|(x, _)| *x).min().unwrap(),
+        "the diagram starts at the origin"
+    );
+}
+
+#[test]
+fn test_pin_layout() {
+    let nodes = vec![
+        LayoutNode::new("a", 100, 60),
+        LayoutNode::new("b", 100, 60),
+        LayoutNode::new("c", 100, 60),
+        LayoutNode::new("d", 100, 60),
+    ];
+    let mut positions: Positions = [
+        ("a", (0, 0)),
+        ("b", (200, 0)),
+        ("c", (400, 0)),
+        ("d", (600, 0)),
+    ]
+    .into_iter()
+    .map(|(name, position)| (name.to_string(), position))
+    .collect();
+    let pins: IndexMap<String, Pin> = serde_yaml::from_str(
+        "
+a: { x: 200, y: 0 }
+c: { anchor: b, dy: 100 }
+d: { anchor: unknown }
+",
+    )
+    .unwrap();
+    assert_eq!(
+        vec!["d".to_string()],
+        pin(&mut positions, &nodes, &pins, 20)
+    );
+    assert_eq!((200, 0), positions["a"]);
+    // `b` makes room for the pinned `a`, `c` follows `b`
+    let (bx, by) = positions["b"];
+    assert!((bx - 200).abs() >= 120 || by.abs() >= 80);
+    assert_eq!((bx, by + 100), positions["c"]);
+    assert_eq!((600, 0), positions["d"]);
+
+    // the unpinned nodes make room for the pin following its anchor
+    let mut positions: Positions = [("a", (0, 0)), ("b", (200, 0)), ("c", (400, 0))]
+        .into_iter()
+        .map(|(name, position)| (name.to_string(), position))
+        .collect();
+    let pins: IndexMap<String, Pin> = serde_yaml::from_str("c: { anchor: a, dx: 200 }").unwrap();
+    assert!(pin(&mut positions, &nodes[..3], &pins, 20).is_empty());
+    assert_eq!((0, 0), positions["a"]);
+    assert_eq!((200, 0), positions["c"]);
+    let overlaps =
+        |(x1, y1): (i32, i32), (x2, y2): (i32, i32)| (x1 - x2).abs() < 100 && (y1 - y2).abs() < 60;
+    assert!(!overlaps(positions["b"], positions["c"]));
+    assert!(!overlaps(positions["b"], positions["a"]));
+
+    let elements: Vec<Value> = serde_json::from_str(
+        r#"[
+        {"type": "rectangle", "id": "container_1", "x": 10.4, "y": 20, "width": 140, "height": 80},
+        {"type": "text", "x": 30, "y": 40, "width": 50, "height": 20, "text": "card line"},
+        {"type": "text", "x": 30, "y": 30, "width": 50, "height": 20, "text": "redis"},
+        {"type": "rectangle", "id": "container_2", "x": 0, "y": 0, "width": 10, "height": 10, "isDeleted": true}
+    ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        IndexMap::from([("redis".to_string(), Pin::Fixed { x: 10, y: 20 })]),
+        extract_pins(&elements)
+    );
+}
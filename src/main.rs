@@ -1,25 +1,33 @@
 mod color_utils;
+mod compose_utils;
+mod env_utils;
 mod error;
 mod exporters;
 mod file_utils;
+mod id_utils;
+mod layout;
+mod routing;
 
-use clap::{arg, command, Parser};
-use exporters::excalidraw::elements::{
-    FONT_SIZE_EXTRA_LARGE, FONT_SIZE_LARGE, FONT_SIZE_MEDIUM, FONT_SIZE_SMALL,
-};
+use clap::Parser;
+use exporters::excalidraw::elements::FONT_SIZE_SMALL;
 use exporters::excalidraw_config::{
     arrow_bounded_element, binding, BoundElement, DEFAULT_CONFIG_PATH,
 };
-use exporters::excalidraw_config::{margins, ExcalidrawConfig};
+use exporters::excalidraw_config::{
+    margins, Alignment, ConnectionStyle, Connections, ExcalidrawConfig, Font,
+};
+use exporters::text_metrics::{measure_text, wrap_text};
+use id_utils::IdGenerator;
 use indexmap::IndexMap;
-use rand::{distributions::Alphanumeric, Rng};
 use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::path::Path;
+use std::process::exit;
 use std::vec;
+use std::{env, fs};
 
 use exporters::excalidraw::{Element, ExcalidrawFile};
 use serde::{Deserialize, Serialize};
-use serde_yaml::Value;
+use serde_yaml::{Mapping, Value};
 
 use crate::error::ExcalidockerError::InvalidDockerCompose;
 use crate::exporters::excalidraw::elements;
@@ -33,7 +41,10 @@ use crate::exporters::excalidraw::elements;
     "
 {} {}
     ╰→ excalidocker --input-path <INPUT_PATH>
-    ╰→ excalidocker --show-config",
+    ╰→ excalidocker --input-path <INPUT_PATH> --input-path <OVERRIDE_INPUT_PATH>
+    ╰→ excalidocker --show-config
+    ╰→ excalidocker --input-path <INPUT_PATH> --update <EXCALIDRAW_PATH>
+    ╰→ excalidocker --extract-layout <EXCALIDRAW_PATH>",
     clap::crate_name!(),
     clap::crate_version!())
 ))]
@@ -41,9 +52,10 @@ struct Cli {
     /// show configuration file
     #[arg(short = 'C', long, default_value_t = false)]
     show_config: bool,
-    /// file path to the docker-compose.yaml
-    #[arg(short, long, required_unless_present = "show_config")]
-    input_path: Option<String>,
+    /// file path to the docker-compose.yaml.
+    /// Could be repeated to merge several files like `docker compose -f a.yml -f b.yml` does
+    #[arg(short, long, required_unless_present_any = ["show_config", "extract_layout"])]
+    input_path: Vec<String>,
     /// display connecting lines between services; if `true` then only service without the lines are rendered
     #[arg(short, long, default_value_t = false)]
     skip_dependencies: bool,
@@ -57,32 +69,163 @@ struct Cli {
     /// config file path for the excalidraw.
     #[arg(short, long, default_value_t = DEFAULT_CONFIG_PATH.to_string())]
     config_path: String,
+    /// profile(s) to enable, services without profiles are always enabled.
+    /// By default `COMPOSE_PROFILES` variable is used
+    #[arg(long)]
+    profile: Vec<String>,
+    /// env file(s) with the variables for the docker-compose interpolation.
+    /// By default the `.env` file next to the docker-compose file is used
+    #[arg(short, long)]
+    env_file: Vec<String>,
+    /// layout file path with the pinned positions of the services.
+    /// By default `<name>.layout.yaml` next to the first docker-compose file is used if it exists,
+    /// services are placed according to the alignment mode without it
+    #[arg(long)]
+    layout_path: Option<String>,
+    /// extract the positions of the services from the existing excalidraw file into the layout file
+    #[arg(long, value_name = "EXCALIDRAW_PATH")]
+    extract_layout: Option<String>,
+    /// existing excalidraw file to update: services keep their positions and sizes,
+    /// the elements added by hand are kept. By default the updated file is written back
+    #[arg(short, long, value_name = "EXCALIDRAW_PATH")]
+    update: Option<String>,
+    /// seed for the ids of the elements and the hand-drawn strokes.
+    /// The same input and seed always give the same output
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
 }
 
 #[derive(Debug, Clone)]
-struct ContainerPoint(String, i32, i32);
+struct DependencyComponent {
+    id: String,
+    parent: Vec<DependencyEdge>,
+}
+
+impl DependencyComponent {
+    fn new(id: String, parent: Vec<DependencyEdge>) -> Self {
+        Self { id, parent }
+    }
+}
+
+/// Kind of the connection between two services
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    DependsOn,
+    Links,
+    VolumesFrom,
+    NetworkMode,
+    Ipc,
+    Pid,
+}
+
+impl EdgeKind {
+    /// Name of the docker-compose attribute which declares the connection
+    fn name(&self) -> &'static str {
+        match self {
+            EdgeKind::DependsOn => "depends_on",
+            EdgeKind::Links => "links",
+            EdgeKind::VolumesFrom => "volumes_from",
+            EdgeKind::NetworkMode => "network_mode",
+            EdgeKind::Ipc => "ipc",
+            EdgeKind::Pid => "pid",
+        }
+    }
 
-impl ContainerPoint {
-    fn new(name: String, x: i32, y: i32) -> Self {
-        Self(name, x, y)
+    /// Style of the connections of this kind
+    fn style<'a>(&self, connections: &'a Connections) -> &'a ConnectionStyle {
+        match self {
+            EdgeKind::DependsOn => &connections.depends_on,
+            EdgeKind::Links => &connections.links,
+            EdgeKind::VolumesFrom => &connections.volumes_from,
+            EdgeKind::NetworkMode => &connections.network_mode,
+            EdgeKind::Ipc => &connections.ipc,
+            EdgeKind::Pid => &connections.pid,
+        }
     }
-    // fn set_name(mut self, name: String) -> Self {
-    //     self.0 = name;
-    //     self
-    // }
 }
 
+/// Connection from a service to the service (`name`) it relies on.
+/// `detail` holds the additional information, f.e. link alias or `volumes_from` access mode.
+/// `condition` and `required` come from the long `depends_on` syntax.
 #[derive(Debug, Clone)]
-struct DependencyComponent {
-    id: String,
+struct DependencyEdge {
     name: String,
-    parent: Vec<DependencyComponent>,
+    kind: EdgeKind,
+    detail: Option<String>,
+    condition: Option<String>,
+    required: bool,
 }
 
-impl DependencyComponent {
-    fn new(id: String, name: String, parent: Vec<DependencyComponent>) -> Self {
-        Self { id, name, parent }
+impl DependencyEdge {
+    fn new(name: String, kind: EdgeKind, detail: Option<String>) -> Self {
+        Self {
+            name,
+            kind,
+            detail,
+            condition: None,
+            required: true,
+        }
+    }
+
+    /// `depends_on` edge, the default `service_started` condition as well as
+    /// the default `required: true` and `restart: false` flags are not included into the detail
+    fn depends_on(dependency: &DockerDependency) -> Self {
+        let mut details = vec![];
+        if dependency.condition != DEFAULT_DEPENDS_ON_CONDITION {
+            details.push(dependency.condition.clone());
+        }
+        if dependency.restart {
+            details.push("restart".to_string());
+        }
+        if !dependency.required {
+            details.push("optional".to_string());
+        }
+        Self {
+            name: dependency.service.clone(),
+            kind: EdgeKind::DependsOn,
+            detail: (!details.is_empty()).then(|| details.join(", ")),
+            condition: Some(dependency.condition.clone()),
+            required: dependency.required,
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!("{}: {}", self.kind.name(), detail),
+            None => self.kind.name().to_string(),
+        }
+    }
+
+    /// Label rendered next to the arrow: the full label when enabled for the connection kind,
+    /// otherwise the non-default `depends_on` condition and flags when the condition labels are enabled
+    fn arrow_label(&self, connections: &Connections) -> Option<String> {
+        if self.kind.style(connections).label {
+            Some(self.label())
+        } else if self.kind == EdgeKind::DependsOn && connections.conditions.label {
+            self.detail.clone()
+        } else {
+            None
+        }
+    }
+}
+
+/// Connections from the same service to the same service (f.e. both `depends_on` and `links`)
+/// are merged to be drawn as a single arrow, the first edge defines its style
+fn merge_connections<'a>(
+    connections: Vec<(&'a str, &'a str, &'a DependencyEdge)>,
+) -> Vec<(&'a str, &'a str, Vec<&'a DependencyEdge>)> {
+    let mut merged: IndexMap<(&str, &str), (&str, Vec<&DependencyEdge>)> = IndexMap::new();
+    for (container_name, id, edge) in connections {
+        merged
+            .entry((container_name, edge.name.as_str()))
+            .or_insert((id, vec![]))
+            .1
+            .push(edge);
     }
+    merged
+        .into_iter()
+        .map(|((container_name, _), (id, edges))| (container_name, id, edges))
+        .collect()
 }
 
 fn traverse_in_hierarchy(
@@ -122,6 +265,9 @@ fn traverse_in_hierarchy(
 //         }
 //     }
 
+/// Number of the cards stacked behind a replicated service
+const REPLICA_LAYERS: i32 = 2;
+
 /// This struct is introduced to hold intermediate state of the rectange
 /// Due to the implementation logic the rectangle initialization (`x`, `y`, `width`, `height`)
 /// is happening in the beginning of the program while `group_ids` and `bound_elements`
@@ -137,6 +283,8 @@ struct RectangleStruct {
     pub group_ids: Vec<String>,
     pub text_group_ids: Vec<String>,
     pub bound_elements: Vec<BoundElement>,
+    /// the name goes to the top when the card text is placed below it
+    pub name_vertical_align: &'static str,
 }
 
 fn main() {
@@ -147,24 +295,78 @@ fn main() {
         println!("{}", serde_yaml::to_string(&excalidraw_config).unwrap());
         return;
     }
+    let layout_path = cli.layout_path.clone().or_else(|| {
+        cli.input_path
+            .first()
+            .and_then(|input_path| file_utils::layout_sidecar_path(input_path))
+    });
+    if let Some(excalidraw_path) = &cli.extract_layout {
+        let Some(layout_path) = layout_path else {
+            println!("Layout file path is unknown, provide --layout-path or a local --input-path");
+            exit(1);
+        };
+        match file_utils::extract_layout(excalidraw_path, &layout_path) {
+            Ok(count) => println!(
+                "\nPositions of {} service(s) are extracted to '{}'\n",
+                count, layout_path
+            ),
+            Err(err) => {
+                println!("{}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    let mut pins = layout_path
+        .map(|layout_path| file_utils::get_layout_pins(&layout_path, cli.layout_path.is_some()))
+        .unwrap_or_default();
+    // services of the drawing being updated keep their positions and sizes
+    let existing_drawing =
+        cli.update
+            .as_ref()
+            .map(|path| match file_utils::read_excalidraw_file(path) {
+                Ok(existing_drawing) => existing_drawing,
+                Err(err) => {
+                    println!("{}", err);
+                    exit(1);
+                }
+            });
+    let existing_services = existing_drawing
+        .as_ref()
+        .map(|drawing| layout::extract_services(&drawing.element_values()))
+        .unwrap_or_default();
+    pins.extend(
+        existing_services
+            .iter()
+            .map(|(name, (x, y, _, _))| (name.clone(), layout::Pin::Fixed { x: *x, y: *y })),
+    );
 
     let mut excalidraw_file = ExcalidrawFile::default();
     let scale = excalidraw_file.app_state.grid_size;
-    let mut x = 0;
-    let mut y = 0;
     let width = 140;
     let height = 60;
     let port_diameter = 60;
 
-    let mut container_name_rectangle_structs = HashMap::new();
-    let mut container_name_to_point = HashMap::new();
+    let mut ids = IdGenerator::new(cli.seed);
+    let mut container_name_rectangle_structs = IndexMap::new();
+    // the ports are avoided by the connections
+    let mut port_obstacles = vec![];
+    // bounding boxes (min x, min y, max x, max y) of the services along with their ports
+    let mut container_name_to_footprint = HashMap::new();
     let mut container_name_to_parents: IndexMap<&str, DependencyComponent> = IndexMap::new();
 
     let mut container_name_to_container_struct = HashMap::new();
 
-    let input_path = &cli.input_path.unwrap();
-    let input_filepath = input_path.as_str();
-    let docker_compose_yaml = file_utils::get_docker_compose_content(input_filepath);
+    // the first file defines the project directory, relative paths are resolved against it
+    let input_filepath = cli.input_path.first().unwrap().as_str();
+    let interpolation_variables =
+        file_utils::get_interpolation_variables(input_filepath, &cli.env_file);
+    let docker_compose_yaml = compose_utils::merge_compose_files(
+        cli.input_path
+            .iter()
+            .map(|path| file_utils::get_docker_compose_content(path, &interpolation_variables))
+            .collect(),
+    );
 
     let alignment_mode = excalidraw_config.alignment.mode.as_str();
     let (x_margin, y_margin, x_alignment_factor, y_alignment_factor) = margins(alignment_mode);
@@ -183,41 +385,330 @@ fn main() {
         }
     };
 
-    let networks = docker_compose_yaml
+    let declared_networks = docker_compose_yaml
         .get("networks")
-        .and_then(DockerContainer::parse_networks);
+        .map(parse_top_level_networks)
+        .unwrap_or_default();
+    let top_level_volumes = docker_compose_yaml
+        .get("volumes")
+        .map(parse_top_level_volumes)
+        .unwrap_or_default();
+    let top_level_secrets = docker_compose_yaml
+        .get("secrets")
+        .map(parse_top_level_resources)
+        .unwrap_or_default();
+    let top_level_configs = docker_compose_yaml
+        .get("configs")
+        .map(parse_top_level_resources)
+        .unwrap_or_default();
+
+    let active_profiles = if cli.profile.is_empty() {
+        interpolation_variables
+            .get("COMPOSE_PROFILES")
+            .map(|profiles| {
+                profiles
+                    .split(',')
+                    .map(|profile| profile.trim().to_string())
+                    .filter(|profile| !profile.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        cli.profile.clone()
+    };
 
-    let mut identifier: i32 = 1;
     for (container_name_val, container_data_val) in services.as_mapping().unwrap() {
-        let container_id = format!("container_{}", identifier);
-
-        let container_struct =
-            DockerContainer::convert_to_container(container_id.clone(), container_data_val);
         let container_name_str = container_name_val.as_str().unwrap();
+        // the id is derived from the service name, so it's stable between the runs
+        let container_id = format!("container_{}", container_name_str);
 
-        let dependency_component = create_dependency_component(
-            container_id,
-            container_name_str.to_string(),
-            &container_struct.depends_on,
+        let container_struct = DockerContainer::convert_to_container(
+            container_id.clone(),
+            container_data_val,
+            input_filepath,
         );
+        if !(container_struct.is_enabled(&active_profiles) || excalidraw_config.profiles.visible) {
+            continue;
+        }
+
+        let dependency_component =
+            DependencyComponent::new(container_id, container_struct.dependency_edges());
         container_name_to_parents.insert(container_name_str, dependency_component);
         container_name_to_container_struct.insert(container_name_str, container_struct);
-        identifier += 1;
+    }
+    // dependencies on the services which are not rendered (f.e. disabled by profiles) are skipped
+    for dependency_component in container_name_to_parents.values_mut() {
+        dependency_component
+            .parent
+            .retain(|parent| container_name_to_container_struct.contains_key(parent.name.as_str()));
     }
 
-    let containers_traversal_order =
+    let show_network = !cli.skip_network && excalidraw_config.network.visible;
+    let networks = collect_networks(
+        declared_networks,
+        container_name_to_parents
+            .keys()
+            .filter_map(|name| container_name_to_container_struct.get(name)),
+        &project_name(
+            &docker_compose_yaml,
+            input_filepath,
+            &interpolation_variables,
+        ),
+        excalidraw_config.network.default_network,
+    );
+    let mut containers_traversal_order =
         find_containers_traversal_order(container_name_to_parents.clone());
+    // members of the same network are placed next to each other, so their boundary encloses
+    // as few other services as possible. The layered layout ranks the services in the traversal order
+    // (parents first) and gets the network order for the services within the ranks
+    let mut network_order = containers_traversal_order.clone();
+    if show_network {
+        order_by_network_membership(
+            &mut network_order,
+            &networks,
+            &container_name_to_container_struct,
+        );
+    }
+    if alignment_mode != "layered" {
+        containers_traversal_order = network_order.clone();
+    }
+    // the lowest point of the services and ports, volumes are placed below it
+    let mut diagram_bottom = 0;
 
-    for cn_name in containers_traversal_order {
+    // cards are measured upfront, so the layouts placing all the services at once can use their sizes
+    let mut container_name_to_card = HashMap::new();
+    for cn_name in &containers_traversal_order {
+        let container_struct = container_name_to_container_struct
+            .get(cn_name.as_str())
+            .unwrap();
+        // resource limits and placement lines go first, network addresses and environment variables after them
+        let mut card_lines = vec![];
+        if excalidraw_config.deploy.resources {
+            card_lines.extend(container_struct.resources_line());
+        }
+        if excalidraw_config.deploy.placement {
+            card_lines.extend(container_struct.placement_lines());
+        }
+        if show_network && excalidraw_config.network.addresses {
+            card_lines.extend(container_struct.network_address_lines());
+        }
+        if excalidraw_config.environment.visible {
+            card_lines.extend(
+                container_struct.environment_lines(&excalidraw_config.environment.redacted_keys),
+            );
+        }
+        let font = &excalidraw_config.font;
+        let (name_width, name_height) = measure_text(cn_name, font.size, font.family);
+        let (card_width, card_height) = if card_lines.is_empty() {
+            (0, 0)
+        } else {
+            measure_text(&card_lines.join("\n"), font.size, font.family)
+        };
+        // the name and the card text are indented by one grid item on both sides
         let container_width =
-            width + find_additional_width(cn_name.as_str(), &scale, &excalidraw_config.font.size);
+            width.max(snap_to_grid(name_width.max(card_width), scale) + 2 * scale);
+        let container_height =
+            height - scale + snap_to_grid(name_height, scale) + snap_to_grid(card_height, scale);
+        let (container_width, container_height) = existing_services.get(cn_name).map_or(
+            (container_width, container_height),
+            |(_, _, width, height)| (*width, *height),
+        );
+        // the narrowed service (see `--update`) wraps its name and grows to fit it
+        let container_height = container_height.max(
+            height - scale
+                + snap_to_grid(get_name_height(cn_name, container_width, font), scale)
+                + snap_to_grid(card_height, scale),
+        );
+        container_name_to_card.insert(
+            cn_name.clone(),
+            (card_lines, container_width, container_height),
+        );
+    }
+
+    let layout_nodes = containers_traversal_order
+        .iter()
+        .map(|cn_name| {
+            let (_, container_width, container_height) = &container_name_to_card[cn_name];
+            // ports are placed below the service card
+            let ports = container_name_to_container_struct[cn_name.as_str()]
+                .ports
+                .as_deref()
+                .unwrap_or_default();
+            let (port_width, port_step) =
+                get_port_width_and_step(ports, port_diameter, scale, &excalidraw_config);
+            let ports_count = ports.len() as i32;
+            let (ports_width, ports_height) = if ports_count > 0 {
+                (
+                    (ports_count - 1) * port_step + port_width,
+                    scale * 5 + port_diameter,
+                )
+            } else {
+                (0, 0)
+            };
+            layout::LayoutNode::new(
+                cn_name,
+                *container_width.max(&ports_width),
+                container_height + ports_height,
+            )
+        })
+        .collect::<Vec<layout::LayoutNode>>();
+    // (child, parent) pairs of the dependency graph
+    let dependency_pairs = container_name_to_parents
+        .iter()
+        .flat_map(|(name, component)| {
+            component
+                .parent
+                .iter()
+                .map(|edge| (name.to_string(), edge.name.clone()))
+        })
+        .collect::<Vec<(String, String)>>();
+    let mut layout_positions = match alignment_mode {
+        "grouped" => {
+            let groups = group_services(
+                &containers_traversal_order,
+                &container_name_to_container_struct,
+                &excalidraw_config.alignment,
+            );
+            let label_height = snap_to_grid(
+                measure_text(
+                    "",
+                    excalidraw_config.font.size,
+                    excalidraw_config.font.family,
+                )
+                .1,
+                scale,
+            ) + scale;
+            let (positions, frames) =
+                layout::grouped(&layout_nodes, &groups, 3 * scale, label_height);
+            for frame in frames {
+                let Some(label) = frame.label else {
+                    continue;
+                };
+                let group_rectangle_id = ids.id("group_rectangle", &[&label]);
+                let mut group_rectangle = Element::simple_rectangle(
+                    group_rectangle_id.clone(),
+                    frame.x,
+                    frame.y,
+                    frame.width,
+                    frame.height,
+                    Vec::new(),
+                    Vec::new(),
+                    elements::BACKGROUND_COLOR.into(),
+                    excalidraw_config.services.fill.clone(),
+                    elements::CONNECTION_STYLE.into(),
+                    excalidraw_config.services.edge.clone(),
+                );
+                let group_text = Element::bound_text(
+                    ids.id("text", &[&group_rectangle_id]),
+                    label,
+                    &mut group_rectangle,
+                    elements::VERTICAL_ALIGN_TOP,
+                    excalidraw_config.font.size,
+                    excalidraw_config.font.family,
+                );
+                excalidraw_file.elements.push(group_rectangle);
+                excalidraw_file.elements.push(group_text);
+            }
+            positions
+        }
+        "layered" => layout::layered(
+            &layout_nodes,
+            &dependency_pairs,
+            &network_order,
+            &excalidraw_config.alignment.direction,
+            3 * scale,
+        ),
+        "force" => {
+            let alignment = &excalidraw_config.alignment;
+            // networks and volumes attract the services attached to them
+            let mut hubs: IndexMap<(&str, String), Vec<String>> = IndexMap::new();
+            for cn_name in &containers_traversal_order {
+                let container_struct = &container_name_to_container_struct[cn_name.as_str()];
+                if alignment.force_networks {
+                    for network in container_struct.network_memberships() {
+                        hubs.entry(("network", network))
+                            .or_default()
+                            .push(cn_name.clone());
+                    }
+                }
+                if alignment.force_volumes {
+                    for volume in container_struct.volumes.iter().flatten() {
+                        if let Some(volume_name) = volume.volume_name() {
+                            hubs.entry(("volume", volume_name.to_string()))
+                                .or_default()
+                                .push(cn_name.clone());
+                        }
+                    }
+                }
+            }
+            layout::force(
+                &layout_nodes,
+                &dependency_pairs,
+                &hubs.into_values().collect::<Vec<Vec<String>>>(),
+                alignment.seed,
+                alignment.iterations,
+                2 * scale,
+            )
+        }
+        "grid" => {
+            let alignment = &excalidraw_config.alignment;
+            let sorted_names = sort_services(
+                &containers_traversal_order,
+                &container_name_to_container_struct,
+                &alignment.sort_by,
+            );
+            let sorted_nodes = sorted_names
+                .iter()
+                .filter_map(|name| layout_nodes.iter().find(|node| &node.name == name))
+                .cloned()
+                .collect::<Vec<layout::LayoutNode>>();
+            let (positions, _, _) = layout::grid_block(
+                &sorted_nodes,
+                alignment.columns,
+                alignment.column_gap,
+                alignment.row_gap,
+            );
+            positions
+        }
+        _ => {
+            // the services are placed step by step following the traversal order
+            let mut positions = layout::Positions::new();
+            let (mut x, mut y) = (0, 0);
+            for cn_name in &containers_traversal_order {
+                positions.insert(cn_name.clone(), (x, y));
+                let (_, container_width, container_height) = &container_name_to_card[cn_name];
+                let (x_alignment, y_alignment) = get_alignment_factor_xy(
+                    alignment_mode,
+                    x_alignment_factor,
+                    y_alignment_factor,
+                    *container_width,
+                    container_height - height,
+                    scale,
+                );
+                x += x_margin + x_alignment;
+                y += y_margin + y_alignment;
+            }
+            positions
+        }
+    };
+    // services pinned by the layout file, the other services are moved out of their way
+    for unresolved in layout::pin(&mut layout_positions, &layout_nodes, &pins, 2 * scale) {
+        eprintln!(
+            "Position of '{}' is not pinned: the anchor is unknown or forms a cycle",
+            unresolved
+        );
+    }
+
+    for cn_name in containers_traversal_order.iter().cloned() {
         let container_struct = container_name_to_container_struct
             .get(cn_name.as_str())
             .unwrap();
-        container_name_to_point.insert(cn_name.clone(), ContainerPoint::new(cn_name.clone(), x, y));
+        let (card_lines, container_width, container_height) =
+            container_name_to_card.remove(&cn_name).unwrap();
+        let (x, y) = layout_positions[&cn_name];
 
         // ------------ Define container ------------
-        let container_group = vec![format!("container_group_{}", generate_id())];
+        let container_group = vec![ids.id("container_group", &[&cn_name])];
 
         let mut rectangle_struct = RectangleStruct {
             id: container_struct.id.clone(),
@@ -225,58 +716,111 @@ fn main() {
             x,
             y,
             width: container_width,
-            height,
+            height: container_height,
             group_ids: container_group.clone(),
             text_group_ids: container_group.clone(),
             bound_elements: vec![],
+            name_vertical_align: if card_lines.is_empty() {
+                elements::VERTICAL_ALIGN_MIDDLE
+            } else {
+                elements::VERTICAL_ALIGN_TOP
+            },
         };
 
+        // ------------ Define resources and environment ------------
+        if !card_lines.is_empty() {
+            // the card text goes below the service name
+            let name_height = get_name_height(&cn_name, container_width, &excalidraw_config.font);
+            let card_text = Element::draw_small_monospaced_text(
+                ids.id("text", &[&cn_name, "card"]),
+                card_lines.join("\n"),
+                x + scale,
+                y + scale + snap_to_grid(name_height, scale),
+                container_group.clone(),
+                excalidraw_config.font.size,
+                excalidraw_config.font.family,
+            );
+            excalidraw_file.elements.push(card_text);
+        }
+
         // ------------ Define ports ------------
         let ports = container_struct.clone().ports.unwrap_or(Vec::new());
+        let mut footprint = (x, y, x + container_width, y + container_height);
+        let (port_width, port_step) =
+            get_port_width_and_step(&ports, port_diameter, scale, &excalidraw_config);
         for (i, port) in ports.iter().enumerate() {
             let i = i as i32;
-            let (container_adjustment_x, container_adjustment_y) =
-                get_container_xy(alignment_mode, &container_width, &scale, i);
+            let (container_adjustment_x, container_adjustment_y) = get_container_xy(
+                alignment_mode,
+                &container_width,
+                &container_height,
+                &scale,
+                i,
+                port_step,
+            );
             let container_x = x + container_adjustment_x;
             let container_y = y + container_adjustment_y;
+            diagram_bottom = diagram_bottom.max(container_y + port_diameter);
+            footprint = (
+                footprint.0.min(container_x),
+                footprint.1.min(container_y),
+                footprint.2.max(container_x + port_width),
+                footprint.3.max(container_y + port_diameter),
+            );
+            port_obstacles.push(routing::Obstacle::new(
+                container_x,
+                container_y,
+                port_width,
+                port_diameter,
+            ));
 
             let (host_port_str, container_port_str) = extract_host_container_ports(port);
             let ellipse_port_group = vec![format!("group_{}_hostport_{}_text", cn_name, i)];
 
-            let ellipse_host_port_id = format!("ellipse_{}", generate_id());
-            let host_port_arrow_id = format!("port_arrow_{}", generate_id());
+            let ellipse_host_port_id =
+                ids.id("ellipse", &[&cn_name, &host_port_str, &container_port_str]);
+            let host_port_arrow_id = ids.id(
+                "port_arrow",
+                &[&cn_name, &host_port_str, &container_port_str],
+            );
 
-            let host_port = Element::draw_ellipse(
+            let mut host_port = Element::draw_ellipse(
                 ellipse_host_port_id.clone(),
                 container_x,
                 container_y,
-                port_diameter,
+                port_width,
                 port_diameter,
                 ellipse_port_group.clone(),
                 vec![arrow_bounded_element(host_port_arrow_id.clone())],
                 excalidraw_config.ports.background_color.clone(),
                 excalidraw_config.ports.fill.clone(),
             );
-            let host_port_text = Element::draw_small_monospaced_text(
+            let host_port_text = Element::bound_text(
+                ids.id("text", &[&ellipse_host_port_id]),
                 host_port_str.clone(),
-                container_x + 15,
-                container_y + 20,
-                ellipse_port_group.clone(),
+                &mut host_port,
+                elements::VERTICAL_ALIGN_MIDDLE,
                 excalidraw_config.font.size,
                 excalidraw_config.font.family,
             );
 
             let (host_port_arrow_x, host_port_arrow_y) =
-                get_host_port_arrow_xy(alignment_mode, &height, &width, &container_width);
-            let host_port_arrow = Element::simple_arrow(
+                get_host_port_arrow_xy(alignment_mode, &container_height, &width, &container_width);
+            let mut host_port_arrow = Element::simple_arrow(
                 host_port_arrow_id.clone(),
                 x + host_port_arrow_x,
                 y + host_port_arrow_y,
                 200,
                 100,
+                elements::STROKE_COLOR.into(),
                 elements::STROKE_STYLE.into(),
                 "sharp".to_string(),
-                get_host_port_arrow_points(alignment_mode, i),
+                get_host_port_arrow_points(
+                    alignment_mode,
+                    i,
+                    port_step,
+                    (port_width - port_diameter) / 2,
+                ),
                 binding(container_struct.id.clone()),
                 binding(ellipse_host_port_id),
             );
@@ -286,77 +830,197 @@ fn main() {
                 .bound_elements
                 .push(arrow_bounded_element(host_port_arrow_id.to_string()));
 
-            if host_port_str != container_port_str {
-                let (container_port_text_x, container_port_text_y) =
-                    get_container_port_text_xy(alignment_mode, &height, &width, i);
-                let container_port_text = Element::draw_small_monospaced_text(
-                    container_port_str,
-                    x + container_port_text_x,
-                    y + container_port_text_y,
-                    container_group.clone(),
-                    excalidraw_config.font.size,
-                    excalidraw_config.font.family,
-                );
-                excalidraw_file.elements.push(container_port_text);
-            }
+            // the container port is placed on the arrow going from the host port
+            let container_port_text =
+                (excalidraw_config.ports.label && host_port_str != container_port_str).then(|| {
+                    Element::bound_text(
+                        ids.id("text", &[&host_port_arrow_id]),
+                        container_port_str,
+                        &mut host_port_arrow,
+                        elements::VERTICAL_ALIGN_MIDDLE,
+                        excalidraw_config.font.size,
+                        excalidraw_config.font.family,
+                    )
+                });
             excalidraw_file.elements.push(host_port);
             excalidraw_file.elements.push(host_port_text);
             excalidraw_file.elements.push(host_port_arrow);
+            excalidraw_file.elements.extend(container_port_text);
         }
 
-        // ------------ Define alignment ------------
-        let (x_alignment, y_alignment) = get_alignment_factor_xy(
-            alignment_mode,
-            x_alignment_factor,
-            y_alignment_factor,
-            container_width,
-            scale,
-        );
-        x += x_margin + x_alignment;
-        y += y_margin + y_alignment;
-
+        diagram_bottom = diagram_bottom.max(rectangle_struct.y + rectangle_struct.height);
+        container_name_to_footprint.insert(cn_name.clone(), footprint);
         container_name_rectangle_structs.insert(cn_name, rectangle_struct);
     }
 
+    // ------------ Define volumes, secrets and configs ------------
+    let mut resource_nodes = vec![];
+    if excalidraw_config.volumes.visible {
+        // volume name -> (mounting container name, mount label)
+        let mut volume_links: IndexMap<String, Vec<(String, String)>> = top_level_volumes
+            .keys()
+            .map(|name| (name.clone(), vec![]))
+            .collect();
+        let mut bind_mount_nodes = vec![];
+        for cn_name in &containers_traversal_order {
+            let container_struct = container_name_to_container_struct
+                .get(cn_name.as_str())
+                .unwrap();
+            for volume in container_struct.volumes.iter().flatten() {
+                if let Some(volume_name) = volume.volume_name() {
+                    volume_links
+                        .entry(volume_name.to_string())
+                        .or_default()
+                        .push((cn_name.clone(), volume.mount_label()));
+                } else if let Some(host_path) = volume
+                    .host_path()
+                    .filter(|_| excalidraw_config.volumes.bind_mounts)
+                {
+                    bind_mount_nodes.push(ResourceNode {
+                        kind: "bind_mount",
+                        label: host_path.to_string(),
+                        links: vec![(cn_name.clone(), volume.mount_label())],
+                        background_color: excalidraw_config
+                            .volumes
+                            .bind_mount_background_color
+                            .clone(),
+                        fill: excalidraw_config.volumes.fill.clone(),
+                        edge: excalidraw_config.volumes.edge.clone(),
+                        stroke_style: elements::CONNECTION_STYLE.to_string(),
+                    });
+                }
+            }
+        }
+        for (volume_name, links) in volume_links {
+            let label = if top_level_volumes.get(&volume_name) == Some(&true) {
+                format!("{} (external)", volume_name)
+            } else {
+                volume_name
+            };
+            resource_nodes.push(ResourceNode {
+                kind: "volume",
+                label,
+                links,
+                background_color: excalidraw_config.volumes.background_color.clone(),
+                fill: excalidraw_config.volumes.fill.clone(),
+                edge: excalidraw_config.volumes.edge.clone(),
+                stroke_style: elements::STROKE_STYLE.to_string(),
+            });
+        }
+        resource_nodes.extend(bind_mount_nodes);
+    }
+    for (kind, style, top_level_resources) in [
+        ("secret", &excalidraw_config.secrets, &top_level_secrets),
+        ("config", &excalidraw_config.configs, &top_level_configs),
+    ] {
+        if !style.visible {
+            continue;
+        }
+        // resource name -> (consuming container name, target path)
+        let mut resource_links: IndexMap<String, Vec<(String, String)>> = top_level_resources
+            .keys()
+            .map(|name| (name.clone(), vec![]))
+            .collect();
+        for cn_name in &containers_traversal_order {
+            let container_struct = container_name_to_container_struct
+                .get(cn_name.as_str())
+                .unwrap();
+            let references = if kind == "secret" {
+                &container_struct.secrets
+            } else {
+                &container_struct.configs
+            };
+            for reference in references.iter().flatten() {
+                resource_links
+                    .entry(reference.source.clone())
+                    .or_default()
+                    .push((cn_name.clone(), reference.target_path(kind)));
+            }
+        }
+        for (name, links) in resource_links {
+            let label = match top_level_resources.get(&name) {
+                Some(Some(origin)) => format!("{}\n{}", name, origin),
+                _ => name,
+            };
+            resource_nodes.push(ResourceNode {
+                kind,
+                label,
+                links,
+                background_color: style.background_color.clone(),
+                fill: style.fill.clone(),
+                edge: style.edge.clone(),
+                stroke_style: elements::STROKE_STYLE.to_string(),
+            });
+        }
+    }
+
+    // the resources are placed below the services and drawn once their links are routed
+    let mut resource_x = 0;
+    let resource_y = diagram_bottom + 3 * scale;
+    let resource_nodes = resource_nodes
+        .into_iter()
+        .map(|resource_node| {
+            let (resource_width, resource_height) =
+                measure_resource_node(&resource_node, &excalidraw_config, scale);
+            let rectangle =
+                routing::Obstacle::new(resource_x, resource_y, resource_width, resource_height);
+            resource_x += resource_width + x_margin.max(scale * 3);
+            (resource_node, rectangle)
+        })
+        .collect::<Vec<_>>();
+
     // ------------ Define network ------------
-    let containers_in_network = if cli.skip_network || !excalidraw_config.network.visible {
-        vec![]
-    } else {
-        find_containers_in_network(
-            container_name_to_parents.clone(),
-            networks,
-            container_name_to_container_struct.clone(),
+    let network_members = if show_network {
+        find_network_members(
+            &networks,
+            &containers_traversal_order,
+            &container_name_to_container_struct,
         )
+    } else {
+        vec![]
     };
 
-    for (network_name, first_container_name, last_container_name) in containers_in_network {
-        let first_container_struct = container_name_rectangle_structs
-            .get(first_container_name.as_str())
-            .unwrap();
-        let last_container_struct = container_name_rectangle_structs
-            .get(last_container_name.as_str())
-            .unwrap();
+    // boundaries sharing members with the already drawn ones are enlarged, so they are nested/overlapped
+    let mut drawn_boundaries: Vec<(&Vec<String>, i32)> = vec![];
+    let mut boundary_obstacles = vec![];
+    for (network, members) in &network_members {
+        let mut network_label = vec![network.label()];
+        if excalidraw_config.network.details {
+            network_label.extend(network.details());
+        }
+        let (label_width, label_height) = measure_text(
+            &network_label.join("\n"),
+            excalidraw_config.font.size,
+            excalidraw_config.font.family,
+        );
+        let label_height = snap_to_grid(label_height, scale) + scale;
+        let overlapping_boundaries = drawn_boundaries
+            .iter()
+            .filter(|(drawn, _)| drawn.iter().any(|member| members.contains(member)))
+            .collect::<Vec<_>>();
+        let level = overlapping_boundaries.len() as i32;
+        // labels of the nested boundaries are stacked
+        let top_offset = label_height
+            + overlapping_boundaries
+                .iter()
+                .map(|(_, height)| height)
+                .sum::<i32>();
+        let member_footprints = members
+            .iter()
+            .filter_map(|member| container_name_to_footprint.get(member))
+            .copied()
+            .collect::<Vec<_>>();
         let (
             network_rectangle_x,
             network_rectangle_y,
             network_rectangle_width,
             network_rectangle_height,
-        ) = get_network_rectangle_xy_width_height(
-            alignment_mode,
-            first_container_struct,
-            last_container_struct,
-            x_margin,
-            y_margin,
-        );
-        let (network_text_x, network_text_y) = get_network_text_xy(
-            alignment_mode,
-            first_container_struct,
-            last_container_struct,
-            x_margin,
-            y_margin,
-        );
-        let network_rectangle = Element::simple_rectangle(
-            format!("network_rectangle_{network_name}"),
+        ) = get_network_rectangle_xy_width_height(&member_footprints, level, top_offset, scale);
+        // the boundary is at least as wide as its label
+        let network_rectangle_width = network_rectangle_width.max(label_width + scale);
+        let network_rectangle_id = format!("network_rectangle_{}", network.name);
+        let mut network_rectangle = Element::simple_rectangle(
+            network_rectangle_id.clone(),
             network_rectangle_x,
             network_rectangle_y,
             network_rectangle_width,
@@ -365,110 +1029,242 @@ fn main() {
             Vec::new(),
             elements::NETWORK_COLOR.into(),
             excalidraw_config.services.fill.clone(),
-            elements::CONNECTION_STYLE.into(),
+            match network.kind {
+                NetworkKind::External => "dotted".to_string(),
+                NetworkKind::Host | NetworkKind::None => elements::STROKE_STYLE.to_string(),
+                NetworkKind::Declared | NetworkKind::Default => {
+                    elements::CONNECTION_STYLE.to_string()
+                }
+            },
             excalidraw_config.services.edge.clone(),
         );
-        let network_text = Element::draw_small_monospaced_text(
-            network_name,
-            network_text_x,
-            network_text_y,
-            Vec::new(),
+        // the label is placed on top of the boundary
+        let network_text = Element::bound_text(
+            ids.id("text", &[&network_rectangle_id]),
+            network_label.join("\n"),
+            &mut network_rectangle,
+            elements::VERTICAL_ALIGN_TOP,
             excalidraw_config.font.size,
             excalidraw_config.font.family,
         );
+        boundary_obstacles.push(routing::Obstacle::new(
+            network_rectangle_x,
+            network_rectangle_y,
+            network_rectangle_width,
+            network_rectangle_height,
+        ));
         excalidraw_file.elements.push(network_rectangle);
         excalidraw_file.elements.push(network_text);
+        drawn_boundaries.push((members, label_height));
     }
 
-    for (
-        container_name,
-        DependencyComponent {
-            id,
-            name: _,
-            parent,
-        },
-    ) in &container_name_to_parents
-    {
-        let ContainerPoint(_, x, y) = container_name_to_point.get(*container_name).unwrap();
-        // any of those two conditions (cli argument or configuration setting) can switch off the connections
-        let sorted_container_points =
-            if cli.skip_dependencies || !excalidraw_config.connections.visible {
-                Vec::<ContainerPoint>::new()
-            } else {
-                let mut points = parent
+    // ------------ Define connections ------------
+    // any of those two conditions (cli argument or configuration setting) can switch off the connections
+    let connections = if cli.skip_dependencies || !excalidraw_config.connections.visible {
+        Vec::<(&str, &str, &DependencyEdge)>::new()
+    } else {
+        container_name_to_parents
+            .iter()
+            .flat_map(|(container_name, component)| {
+                component
+                    .parent
                     .iter()
-                    .map(|dc| {
-                        let cp = container_name_to_point.get(&dc.name).unwrap();
-                        ContainerPoint::new(dc.name.clone(), cp.1, cp.2)
-                    })
-                    .collect::<Vec<ContainerPoint>>();
-                points.sort_by(|cp1, cp2| cp2.1.cmp(&cp1.1));
-                points
-            };
-
-        for (i, parent_point) in sorted_container_points.iter().enumerate() {
-            let i = i as i32;
-            let parent_name = &parent_point.0;
-            let parent_temp_struct = container_name_rectangle_structs
-                .get_mut(parent_name)
-                .unwrap();
-
-            let x_parent = &parent_point.1;
-            let y_parent = &parent_point.2;
-            let level_height = y_parent - y;
-            let interation_x_margin = (i + 1) * scale;
-
-            let connecting_arrow_points = get_connecting_arrow_points(
-                alignment_mode,
-                x,
-                y,
-                x_parent,
-                y_parent,
-                &height,
-                &width,
-                &interation_x_margin,
-                &scale,
-                level_height,
-                i,
-            );
-            let connecting_arrow_id = format!("connecting_arrow_{}", generate_id());
-            let (connecting_arrow_x, connecting_arrow_y) =
-                get_connecting_arrow_xy(alignment_mode, interation_x_margin);
-            let connecting_arrow = Element::simple_arrow(
-                connecting_arrow_id.clone(),
-                x + connecting_arrow_x,
-                y + connecting_arrow_y,
-                0,
-                y_margin,
-                elements::CONNECTION_STYLE.into(),
-                excalidraw_config.connections.edge.clone(),
-                connecting_arrow_points,
-                binding(id.to_string()),                // child container
-                binding(parent_temp_struct.id.clone()), // parent container
-            );
-
-            // for dependency connection we need to add:
-            // - child container id to the binding
-            // - parent container id to the binding
-            // - boundElements for the child container (id of the connecting_arrow)
-            // - boundElements for the parent container (id of the connecting_arrow)
+                    .filter(|edge| edge.kind.style(&excalidraw_config.connections).visible)
+                    .map(move |edge| (*container_name, component.id.as_str(), edge))
+            })
+            .collect()
+    };
+    let connections = merge_connections(connections);
+    // the services and the resources come first among the obstacles, so the connections and
+    // the resource links can refer to them by index
+    let service_names = containers_traversal_order
+        .iter()
+        .filter(|name| container_name_rectangle_structs.contains_key(name.as_str()))
+        .collect::<Vec<_>>();
+    let obstacles = service_names
+        .iter()
+        .map(|name| {
+            let rect = &container_name_rectangle_structs[name.as_str()];
+            routing::Obstacle::new(rect.x, rect.y, rect.width, rect.height)
+        })
+        .chain(resource_nodes.iter().map(|(_, rectangle)| *rectangle))
+        .chain(port_obstacles)
+        .collect::<Vec<_>>();
+    let service_index = |name: &str| service_names.iter().position(|n| n.as_str() == name);
+    let connections = connections
+        .into_iter()
+        .filter_map(|(container_name, id, edges)| {
+            Some((
+                (
+                    service_index(container_name)?,
+                    service_index(&edges[0].name)?,
+                ),
+                (container_name, id, edges),
+            ))
+        })
+        .collect::<Vec<_>>();
+    // every link goes from the service to the resource
+    let resource_links = resource_nodes
+        .iter()
+        .enumerate()
+        .flat_map(|(index, (resource_node, _))| {
+            let resource_index = service_names.len() + index;
+            resource_node
+                .links
+                .iter()
+                .map(move |(cn_name, _)| (cn_name, resource_index))
+        })
+        .map(|(cn_name, resource_index)| (service_index(cn_name).unwrap(), resource_index))
+        .collect::<Vec<_>>();
+    let mut routes = routing::Router::new(obstacles, boundary_obstacles, scale).route_all(
+        &connections
+            .iter()
+            .map(|(indices, _)| *indices)
+            .chain(resource_links)
+            .collect::<Vec<_>>(),
+    );
+    let mut resource_routes = routes.split_off(connections.len()).into_iter();
+
+    for ((_, (container_name, id, edges)), route) in connections.iter().zip(routes) {
+        let edge = edges[0];
+        let connection_style = edge.kind.style(&excalidraw_config.connections);
+        let conditions = &excalidraw_config.connections.conditions;
+        let stroke_color = edge
+            .condition
+            .as_deref()
+            .and_then(|condition| conditions.stroke_color(condition))
+            .unwrap_or(&connection_style.stroke_color)
+            .clone();
+        let stroke_style = if edge.required {
+            connection_style.stroke_style.clone()
+        } else {
+            conditions.optional_stroke_style.clone()
+        };
+        let labels = edges
+            .iter()
+            .filter_map(|edge| edge.arrow_label(&excalidraw_config.connections))
+            .collect::<Vec<_>>();
+        let label = (!labels.is_empty()).then(|| labels.join("\n"));
+        let (
+            connecting_arrow_x,
+            connecting_arrow_y,
+            arrow_width,
+            arrow_height,
+            connecting_arrow_points,
+        ) = arrow_geometry(&route);
+        let connecting_arrow_id = ids.id(
+            "connecting_arrow",
+            &[
+                container_name,
+                &edge.name,
+                &edges
+                    .iter()
+                    .map(|edge| edge.label())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ],
+        );
+        let parent_temp_struct = container_name_rectangle_structs
+            .get_mut(&edge.name)
+            .unwrap();
+        let mut connecting_arrow = Element::simple_arrow(
+            connecting_arrow_id.clone(),
+            connecting_arrow_x,
+            connecting_arrow_y,
+            arrow_width,
+            arrow_height,
+            stroke_color,
+            stroke_style,
+            excalidraw_config.connections.edge.clone(),
+            connecting_arrow_points,
+            binding(id.to_string()),                // child container
+            binding(parent_temp_struct.id.clone()), // parent container
+        );
+        // the label is placed over the middle of the route
+        let connection_text = label.map(|label| {
+            Element::bound_text(
+                ids.id("text", &[&connecting_arrow_id]),
+                label,
+                &mut connecting_arrow,
+                elements::VERTICAL_ALIGN_MIDDLE,
+                FONT_SIZE_SMALL,
+                excalidraw_config.font.family,
+            )
+        });
+
+        // for dependency connection we need to add:
+        // - child container id to the binding
+        // - parent container id to the binding
+        // - boundElements for the child container (id of the connecting_arrow)
+        // - boundElements for the parent container (id of the connecting_arrow)
+
+        let connecting_arrow_bound = arrow_bounded_element(connecting_arrow_id);
+        parent_temp_struct
+            .bound_elements
+            .push(connecting_arrow_bound.clone());
+        let current_temp_struct = container_name_rectangle_structs
+            .get_mut(*container_name)
+            .unwrap();
+        current_temp_struct
+            .bound_elements
+            .push(connecting_arrow_bound);
+        excalidraw_file.elements.push(connecting_arrow);
+        excalidraw_file.elements.extend(connection_text);
+    }
 
-            let connecting_arrow_bound = arrow_bounded_element(connecting_arrow_id);
-            parent_temp_struct
-                .bound_elements
-                .push(connecting_arrow_bound.clone());
-            let current_temp_struct = container_name_rectangle_structs
-                .get_mut(*container_name)
-                .unwrap();
-            current_temp_struct
-                .bound_elements
-                .push(connecting_arrow_bound);
-            excalidraw_file.elements.push(connecting_arrow);
-        }
+    for (resource_node, rectangle) in &resource_nodes {
+        draw_resource_node(
+            resource_node,
+            rectangle,
+            resource_routes.by_ref().take(resource_node.links.len()),
+            &mut container_name_rectangle_structs,
+            &excalidraw_config,
+            &mut ids,
+            &mut excalidraw_file.elements,
+        );
     }
 
     container_name_rectangle_structs.values().for_each(|rect| {
-        let container_rectangle = Element::simple_rectangle(
+        let container_struct = container_name_to_container_struct.get(rect.container_name.as_str());
+        let profiles = container_struct
+            .and_then(|container| container.profiles.clone())
+            .filter(|_| excalidraw_config.profiles.visible)
+            .unwrap_or_default();
+        let (background_color, stroke_style) = if profiles.is_empty() {
+            (
+                excalidraw_config.services.background_color.clone(),
+                elements::STROKE_STYLE.to_string(),
+            )
+        } else {
+            (
+                excalidraw_config.profiles.background_color.clone(),
+                excalidraw_config.profiles.stroke_style.clone(),
+            )
+        };
+        let replicas_badge = container_struct
+            .and_then(|container| container.replicas_badge())
+            .filter(|_| excalidraw_config.deploy.replicas);
+        if replicas_badge.is_some() {
+            // the replicas are drawn as the cards stacked behind the service card
+            for layer in (1..=REPLICA_LAYERS).rev() {
+                let replica_offset = layer * scale / 4;
+                let replica_rectangle = Element::simple_rectangle(
+                    ids.id("replica", &[&rect.container_name, &layer.to_string()]),
+                    rect.x + replica_offset,
+                    rect.y + replica_offset,
+                    rect.width,
+                    rect.height,
+                    rect.group_ids.clone(),
+                    Vec::new(),
+                    background_color.clone(),
+                    excalidraw_config.services.fill.clone(),
+                    stroke_style.clone(),
+                    excalidraw_config.services.edge.clone(),
+                );
+                excalidraw_file.elements.push(replica_rectangle);
+            }
+        }
+        let mut container_rectangle = Element::simple_rectangle(
             rect.id.clone(),
             rect.x,
             rect.y,
@@ -476,28 +1272,86 @@ fn main() {
             rect.height,
             rect.group_ids.clone(),
             rect.bound_elements.clone(),
-            excalidraw_config.services.background_color.clone(),
+            background_color,
             excalidraw_config.services.fill.clone(),
-            elements::STROKE_STYLE.into(),
+            stroke_style,
             excalidraw_config.services.edge.clone(),
         );
-        let container_text = Element::draw_small_monospaced_text(
+        if !profiles.is_empty() {
+            let profile_text = Element::draw_small_monospaced_text(
+                ids.id("text", &[&rect.container_name, "profiles"]),
+                format!("profiles: {}", profiles.join(", ")),
+                rect.x,
+                rect.y - scale,
+                rect.text_group_ids.clone(),
+                FONT_SIZE_SMALL,
+                excalidraw_config.font.family,
+            );
+            excalidraw_file.elements.push(profile_text);
+        }
+        let container_text = Element::bound_text(
+            ids.id("text", &[&rect.id]),
             rect.container_name.clone(),
-            rect.x + scale,
-            rect.y + scale,
-            rect.text_group_ids.clone(),
+            &mut container_rectangle,
+            rect.name_vertical_align,
             excalidraw_config.font.size,
             excalidraw_config.font.family,
         );
         excalidraw_file.elements.push(container_rectangle);
         excalidraw_file.elements.push(container_text);
+        if let Some(replicas_badge) = replicas_badge {
+            // the badge is placed in the bottom right corner of the service
+            let replicas_text = Element::draw_small_monospaced_text(
+                ids.id("text", &[&rect.container_name, "replicas"]),
+                replicas_badge,
+                rect.x + rect.width - 2 * scale,
+                rect.y + rect.height - scale,
+                rect.text_group_ids.clone(),
+                FONT_SIZE_SMALL,
+                excalidraw_config.font.family,
+            );
+            excalidraw_file.elements.push(replicas_text);
+        }
+        if excalidraw_config.healthcheck.visible
+            && container_struct.is_some_and(|container| container.healthcheck)
+        {
+            // the marker is placed in the top right corner of the service
+            let marker_diameter = scale / 2;
+            let healthcheck_marker = Element::draw_ellipse(
+                ids.id("healthcheck", &[&rect.container_name]),
+                rect.x + rect.width - scale,
+                rect.y + marker_diameter,
+                marker_diameter,
+                marker_diameter,
+                rect.text_group_ids.clone(),
+                Vec::new(),
+                excalidraw_config.healthcheck.background_color.clone(),
+                "solid".to_string(),
+            );
+            excalidraw_file.elements.push(healthcheck_marker);
+        }
     });
+    // generated elements are marked, so they can be replaced on update
+    let service_ids = container_name_rectangle_structs
+        .values()
+        .map(|rect| (rect.id.as_str(), rect.container_name.as_str()))
+        .collect::<HashMap<&str, &str>>();
+    for element in excalidraw_file.elements.iter_mut() {
+        let service = element.id().and_then(|id| service_ids.get(id)).copied();
+        element.mark_generated(service);
+        if let Some(key) = element.id().map(str::to_string) {
+            element.set_seed(id_utils::element_seed(cli.seed, &key));
+        }
+    }
+    if let Some(existing_drawing) = existing_drawing {
+        excalidraw_file = excalidraw_file.update(existing_drawing);
+    }
     let excalidraw_data = serde_json::to_string(&excalidraw_file).unwrap();
-    match cli.output_path {
+    match cli.output_path.or(cli.update) {
         Some(output_file_path) => {
             fs::write(output_file_path.clone(), excalidraw_data).expect("Unable to write file");
             println!("\nConfiguration file : '{}'", cli.config_path.as_str());
-            println!("\nInput file : '{}'", input_filepath);
+            println!("\nInput file : '{}'", cli.input_path.join("', '"));
             println!(
                 "\nExcalidraw file is successfully generated and can be found at '{}'\n",
                 output_file_path
@@ -507,74 +1361,195 @@ fn main() {
     }
 }
 
-fn create_dependency_component(
-    id: String,
-    container_name: String,
-    depends_on: &Option<Vec<String>>,
-) -> DependencyComponent {
-    match depends_on {
-        Some(deps) => DependencyComponent::new(
-            id,
-            container_name,
-            deps.iter()
-                .map(|name| DependencyComponent::new("".to_string(), name.to_string(), Vec::new()))
-                .collect::<Vec<DependencyComponent>>(),
-        ),
-        None => DependencyComponent::new(id, container_name, Vec::new()),
+/// Volume, secret or config rendered as a separate rectangle below the services.
+/// `links` hold the names of the services using the resource along with the link label.
+struct ResourceNode {
+    kind: &'static str,
+    label: String,
+    links: Vec<(String, String)>,
+    background_color: String,
+    fill: String,
+    edge: String,
+    stroke_style: String,
+}
+
+/// Width and height of the resource rectangle
+fn measure_resource_node(
+    resource_node: &ResourceNode,
+    excalidraw_config: &ExcalidrawConfig,
+    scale: i32,
+) -> (i32, i32) {
+    let (label_width, label_height) = measure_text(
+        &resource_node.label,
+        excalidraw_config.font.size,
+        excalidraw_config.font.family,
+    );
+    (
+        (scale * 7).max(snap_to_grid(label_width, scale) + 2 * scale),
+        scale * 2 + snap_to_grid(label_height, scale),
+    )
+}
+
+/// Draws the resource rectangle and connects it with the services using it along the `routes`
+/// (one per link)
+fn draw_resource_node(
+    resource_node: &ResourceNode,
+    rectangle: &routing::Obstacle,
+    routes: impl Iterator<Item = Vec<[i32; 2]>>,
+    container_name_rectangle_structs: &mut IndexMap<String, RectangleStruct>,
+    excalidraw_config: &ExcalidrawConfig,
+    ids: &mut IdGenerator,
+    elements: &mut Vec<Element>,
+) {
+    let resource_id = ids.id(resource_node.kind, &[&resource_node.label]);
+    let resource_group = vec![ids.id(
+        &format!("{}_group", resource_node.kind),
+        &[&resource_node.label],
+    )];
+    let mut resource_bound_elements = vec![];
+
+    for ((cn_name, link_label), route) in resource_node.links.iter().zip(routes) {
+        let container_rectangle = container_name_rectangle_structs
+            .get_mut(cn_name.as_str())
+            .unwrap();
+        let resource_arrow_id = ids.id(
+            &format!("{}_arrow", resource_node.kind),
+            &[&resource_node.label, cn_name, link_label],
+        );
+        let (arrow_x, arrow_y, arrow_width, arrow_height, arrow_points) = arrow_geometry(&route);
+        let mut resource_arrow = Element::simple_arrow(
+            resource_arrow_id.clone(),
+            arrow_x,
+            arrow_y,
+            arrow_width,
+            arrow_height,
+            elements::STROKE_COLOR.into(),
+            elements::CONNECTION_STYLE.into(),
+            "sharp".to_string(),
+            arrow_points,
+            binding(container_rectangle.id.clone()),
+            binding(resource_id.clone()),
+        );
+        let link_text = Element::bound_text(
+            ids.id("text", &[&resource_arrow_id]),
+            link_label.clone(),
+            &mut resource_arrow,
+            elements::VERTICAL_ALIGN_MIDDLE,
+            FONT_SIZE_SMALL,
+            excalidraw_config.font.family,
+        );
+        container_rectangle
+            .bound_elements
+            .push(arrow_bounded_element(resource_arrow_id.clone()));
+        resource_bound_elements.push(arrow_bounded_element(resource_arrow_id));
+        elements.push(resource_arrow);
+        elements.push(link_text);
     }
+
+    let mut resource_rectangle = Element::simple_rectangle(
+        resource_id.clone(),
+        rectangle.x,
+        rectangle.y,
+        rectangle.width,
+        rectangle.height,
+        resource_group.clone(),
+        resource_bound_elements,
+        resource_node.background_color.clone(),
+        resource_node.fill.clone(),
+        resource_node.stroke_style.clone(),
+        resource_node.edge.clone(),
+    );
+    let resource_text = Element::bound_text(
+        ids.id("text", &[&resource_id]),
+        resource_node.label.clone(),
+        &mut resource_rectangle,
+        elements::VERTICAL_ALIGN_MIDDLE,
+        excalidraw_config.font.size,
+        excalidraw_config.font.family,
+    );
+    elements.push(resource_rectangle);
+    elements.push(resource_text);
 }
 
-fn get_connecting_arrow_xy(alignment_mode: &str, interation_margin: i32) -> (i32, i32) {
-    if alignment_mode == "vertical" {
-        (0, interation_margin / 2)
-    } else {
-        (interation_margin, 0)
+/// Position, width, height and the relative points of the arrow drawn along the route
+fn arrow_geometry(route: &[[i32; 2]]) -> (i32, i32, i32, i32, Vec<[i32; 2]>) {
+    let [x, y] = route[0];
+    let points = route
+        .iter()
+        .map(|[point_x, point_y]| [point_x - x, point_y - y])
+        .collect::<Vec<_>>();
+    let extent = |coordinate: fn(&[i32; 2]) -> i32| {
+        points.iter().map(coordinate).max().unwrap_or(0)
+            - points.iter().map(coordinate).min().unwrap_or(0)
+    };
+    let width = extent(|[x, _]| *x);
+    let height = extent(|[_, y]| *y);
+    (x, y, width, height, points)
+}
+
+/// Order of the services for the `grid` alignment, services are sorted `by`:
+///  - `name` - service name
+///  - `network` - the first network of the service, services without networks go last
+///  - `traversal` - the dependencies traversal order (default)
+fn sort_services(
+    traversal_order: &[String],
+    container_name_to_container_struct: &HashMap<&str, DockerContainer>,
+    by: &str,
+) -> Vec<String> {
+    let mut sorted = traversal_order.to_vec();
+    match by {
+        "name" => sorted.sort(),
+        "network" => sorted.sort_by_key(|name| {
+            container_name_to_container_struct[name.as_str()]
+                .network_memberships()
+                .first()
+                .cloned()
+                .map_or((1, String::new()), |network| (0, network))
+        }),
+        _ => (),
     }
+    sorted
 }
 
-#[allow(clippy::too_many_arguments)]
-fn get_connecting_arrow_points(
-    alignment_mode: &str,
-    x: &i32,
-    y: &i32,
-    x_parent: &i32,
-    y_parent: &i32,
-    height: &i32,
-    width: &i32,
-    interation_x_margin: &i32,
-    scale: &i32,
-    level_height: i32,
-    i: i32,
-) -> Vec<[i32; 2]> {
-    if alignment_mode == "vertical" {
-        vec![
-            [0, 0],
-            [-2 * (i + 1) * scale, 0],
-            [
-                -2 * (i + 1) * scale,
-                // level_height
-                level_height + scale,
-            ],
-            [
-                -1,
-                // level_height
-                level_height + scale,
-            ],
-        ]
-    } else {
-        vec![
-            [0, 0],
-            [0, level_height - height],
-            [
-                -x + x_parent + width - interation_x_margin * 2,
-                level_height - height,
-            ],
-            [
-                -x + x_parent + width - interation_x_margin * 2,
-                y_parent - y,
-            ],
-        ]
+/// Groups of the services for the `grouped` alignment, services are grouped `by`:
+///  - `network` - the first network of the service (groups are not labeled, networks are rendered anyway)
+///  - `label` - the value of the `label` service label (services without it are not labeled)
+///  - `prefix` - the part of the service name before the `separator`
+fn group_services(
+    traversal_order: &[String],
+    container_name_to_container_struct: &HashMap<&str, DockerContainer>,
+    alignment: &Alignment,
+) -> Vec<(Option<String>, Vec<String>)> {
+    let mut groups: IndexMap<(String, Option<String>), Vec<String>> = IndexMap::new();
+    for name in traversal_order {
+        let container = &container_name_to_container_struct[name.as_str()];
+        let key = match alignment.group_by.as_str() {
+            "label" => {
+                let label = container.labels.get(&alignment.group_label).cloned();
+                (label.clone().unwrap_or_default(), label)
+            }
+            "prefix" => {
+                let prefix = name
+                    .split_once(alignment.group_separator.as_str())
+                    .map_or(name.as_str(), |(prefix, _)| prefix)
+                    .to_string();
+                (prefix.clone(), Some(prefix))
+            }
+            _ => (
+                container
+                    .network_memberships()
+                    .first()
+                    .cloned()
+                    .unwrap_or_default(),
+                None,
+            ),
+        };
+        groups.entry(key).or_default().push(name.clone());
     }
+    groups
+        .into_iter()
+        .map(|((_, label), names)| (label, names))
+        .collect()
 }
 
 fn get_alignment_factor_xy(
@@ -582,36 +1557,30 @@ fn get_alignment_factor_xy(
     x_alignment_factor: i32,
     y_alignment_factor: i32,
     container_width: i32,
+    container_extra_height: i32,
     scale: i32,
 ) -> (i32, i32) {
     (
         x_alignment_factor * container_width,
-        if alignment_mode == "vertical" {
-            y_alignment_factor * 2 * scale // TODO should we increase the step or make it configurable??
-        } else {
-            y_alignment_factor * scale
+        match alignment_mode {
+            "vertical" => y_alignment_factor * 2 * scale + container_extra_height, // TODO should we increase the step or make it configurable??
+            "horizontal" => y_alignment_factor * scale,
+            _ => y_alignment_factor * scale + container_extra_height,
         },
     )
 }
 
-fn get_container_port_text_xy(
+/// `port_shift` moves the arrow end to the middle of the ellipses wider than the default
+fn get_host_port_arrow_points(
     alignment_mode: &str,
-    height: &i32,
-    width: &i32,
     i: i32,
-) -> (i32, i32) {
-    if alignment_mode == "vertical" {
-        (width + 20, height / 2 + (i * 40) - 35)
-    } else {
-        (20 + i * 80, 80)
-    }
-}
-
-fn get_host_port_arrow_points(alignment_mode: &str, i: i32) -> Vec<[i32; 2]> {
+    port_step: i32,
+    port_shift: i32,
+) -> Vec<[i32; 2]> {
     if alignment_mode == "vertical" {
-        vec![[0, 0], [i + 100, i * 80 - 35]]
+        vec![[0, 0], [i + 100 + port_shift, i * 80 - 35]]
     } else {
-        vec![[0, 0], [i * 80 - 35, i + 100]]
+        vec![[0, 0], [i * port_step - 35 + port_shift, i + 100]]
     }
 }
 
@@ -628,90 +1597,103 @@ fn get_host_port_arrow_xy(
     }
 }
 
-fn get_container_xy(alignment_mode: &str, width: &i32, scale: &i32, i: i32) -> (i32, i32) {
+fn get_container_xy(
+    alignment_mode: &str,
+    width: &i32,
+    height: &i32,
+    scale: &i32,
+    i: i32,
+    port_step: i32,
+) -> (i32, i32) {
     if alignment_mode == "vertical" {
         (*width + scale * 5, i * 80 - 35)
     } else {
-        (i * 80, scale * 8)
+        (i * port_step, height + scale * 5)
     }
 }
 
+/// Width of the port ellipses of a service, wide enough for the longest host port,
+/// along with the distance between the neighbouring ports
+fn get_port_width_and_step(
+    ports: &[DockerPort],
+    port_diameter: i32,
+    scale: i32,
+    excalidraw_config: &ExcalidrawConfig,
+) -> (i32, i32) {
+    let port_width = ports
+        .iter()
+        .map(|port| {
+            let (host_port, _) = extract_host_container_ports(port);
+            let (text_width, _) = measure_text(
+                &host_port,
+                excalidraw_config.font.size,
+                excalidraw_config.font.family,
+            );
+            // the label fits the square inscribed into the ellipse
+            let label_width = (text_width + 2 * elements::BOUND_TEXT_PADDING) as f64;
+            snap_to_grid(
+                (label_width * std::f64::consts::SQRT_2).ceil() as i32,
+                scale,
+            )
+        })
+        .fold(port_diameter, i32::max);
+    (port_width, (port_width + scale).max(80))
+}
+
+/// Height of the service name bound to the service of the given width, the long names are wrapped
+fn get_name_height(name: &str, container_width: i32, font: &Font) -> i32 {
+    let max_width = container_width - 2 * elements::BOUND_TEXT_PADDING;
+    measure_text(
+        &wrap_text(name, max_width, font.size, font.family),
+        font.size,
+        font.family,
+    )
+    .1
+}
+
+/// Rounds the size up to the multiple of the grid item
+fn snap_to_grid(size: i32, scale: i32) -> i32 {
+    (size + scale - 1) / scale * scale
+}
+
+/// Bounding box of the network members footprints (min x, min y, max x, max y).
+/// The padding grows with the nesting `level`, the `top_offset` leaves space for the network label(s).
 fn get_network_rectangle_xy_width_height(
-    alignment_mode: &str,
-    first_container_struct: &RectangleStruct,
-    last_container_struct: &RectangleStruct,
-    x_margin: i32,
-    y_margin: i32,
+    member_footprints: &[(i32, i32, i32, i32)],
+    level: i32,
+    top_offset: i32,
+    scale: i32,
 ) -> (i32, i32, i32, i32) {
-    let first_x = first_container_struct.x;
-    let first_y = first_container_struct.y;
-    let last_x = last_container_struct.x;
-    let last_y = last_container_struct.y;
-    let last_width = last_container_struct.width;
-    let last_height = last_container_struct.height;
-    match alignment_mode {
-        "stepped" => (
-            first_x - x_margin / 2,
-            first_y - y_margin / 2,
-            (last_x - first_x) + last_width + x_margin,
-            (last_y - first_y) + last_height + y_margin,
-        ),
-        "vertical" => (
-            first_x - x_margin / 4 - 40,
-            first_y - y_margin / 4,
-            (last_x - first_x + 2 * 40) + last_width + x_margin,
-            (last_y - first_y) + last_height + y_margin / 2,
-        ),
-        "horizontal" => (
-            first_x - x_margin / 2,
-            first_y - y_margin / 2 - 40,
-            (last_x - first_x) + last_width + x_margin,
-            (last_y - first_y + 2 * 40) + last_height + y_margin,
-        ),
-        _ => (0_i32, 0_i32, 0_i32, 0_i32),
-    }
+    let padding = scale + level * scale / 2;
+    let min_x = member_footprints.iter().map(|f| f.0).min().unwrap_or(0);
+    let min_y = member_footprints.iter().map(|f| f.1).min().unwrap_or(0);
+    let max_x = member_footprints.iter().map(|f| f.2).max().unwrap_or(0);
+    let max_y = member_footprints.iter().map(|f| f.3).max().unwrap_or(0);
+    (
+        min_x - padding,
+        min_y - padding - top_offset,
+        max_x - min_x + 2 * padding,
+        max_y - min_y + 2 * padding + top_offset,
+    )
 }
 
-fn get_network_text_xy(
-    alignment_mode: &str,
-    first_container_struct: &RectangleStruct,
-    last_container_struct: &RectangleStruct,
-    x_margin: i32,
-    y_margin: i32,
-) -> (i32, i32) {
-    let first_x = first_container_struct.x;
-    let first_y = first_container_struct.y;
-    let last_y = last_container_struct.y;
-    let last_height = last_container_struct.height;
-    match alignment_mode {
-        "stepped" => (first_x - x_margin / 2, last_y - last_height - y_margin),
-        "vertical" => (first_x + x_margin - 6 * 20, first_y),
-        "horizontal" => (first_x - x_margin / 2, last_y - last_height - y_margin),
-        _ => (0_i32, 0_i32),
-    }
-}
-
-/// There are several ways to declare ports:
-///  - "0" single port value(range of values): a container port(range) will be assigned to random host port(range)
-///  - "1" colon separated values (range of values): container port (range) is assigned to given host port (range)
-///  - "_" detailed declaration which may include `host_ip`, `protocol` etc
-fn extract_host_container_ports(port: &str) -> (String, String) {
-    let port_parts: Vec<_> = port.rmatch_indices(':').collect();
-    let port_string = port.to_string();
-    match port_parts.len() {
-        0 => (port_string.clone(), port_string),
-        1 => {
-            let split = port.split(':').collect::<Vec<&str>>();
-            (split[0].to_string(), split[1].to_string())
-        }
-        _ => {
-            let colon_index = port_parts.first().unwrap().0;
-            (
-                port_string.chars().take(colon_index).collect(),
-                port_string.chars().skip(colon_index + 1).collect(),
-            )
-        }
-    }
+/// Returns the (host, container) labels for the given port:
+///  - the container label is the `target` port (range) with the `protocol` (if any)
+///  - the host label is the `published` port (range) prefixed with the `host_ip` (if any)
+///  - without `published` the port is assigned to a random host port (range): the host label
+///    repeats the container one, or is the `host_ip` alone when the port is bound to it
+fn extract_host_container_ports(port: &DockerPort) -> (String, String) {
+    let container_port = match &port.protocol {
+        Some(protocol) => format!("{}/{}", port.target, protocol),
+        None => port.target.clone(),
+    };
+    let host_port = match (&port.host_ip, &port.published) {
+        (Some(host_ip), Some(published)) => format!("{}:{}", host_ip, published),
+        (Some(host_ip), None) => host_ip.clone(),
+        (None, Some(published)) => published.clone(),
+        (None, None) => container_port.clone(),
+    };
+    (host_port, container_port)
 }
 
 fn find_containers_traversal_order(
@@ -730,46 +1712,80 @@ fn find_containers_traversal_order(
     containers_traversal_order
 }
 
-fn find_containers_in_network(
-    container_name_to_parents: IndexMap<&str, DependencyComponent>,
-    networks: Option<Vec<String>>,
-    container_name_to_container_struct: HashMap<&str, DockerContainer>,
-) -> Vec<(String, String, String)> {
-    let traversal_order = find_containers_traversal_order(container_name_to_parents.clone());
-    match networks {
-        Some(networks) => match networks.len() {
-            0 => vec![],
-            1 => vec![(
-                networks.first().unwrap().to_string(),
-                traversal_order.first().unwrap().to_string(),
-                traversal_order.last().unwrap().to_string(),
-            )],
-            _ => {
-                // here, based on `traversal_order` we need to find first and last for each network
-                let mut result = vec![];
-                for network in networks {
-                    let containers_within_network: Vec<String> = traversal_order
-                        .iter()
-                        .filter(|name| {
-                            is_in_network(
-                                &container_name_to_container_struct.clone(),
-                                name.to_string(),
-                                network.clone(),
-                            )
-                        })
-                        .cloned()
-                        .collect();
-                    result.push((
-                        network,
-                        containers_within_network.first().unwrap().to_string(),
-                        containers_within_network.last().unwrap().to_string(),
-                    ))
-                }
-                result
-            }
-        },
-        None => vec![],
+/// Stable reordering of the services by the networks they belong to
+/// (the first and the last of their networks in the chain order, see below).
+/// Thus the services sharing a network follow each other and the services
+/// bridging two networks are placed between the members of those networks.
+/// Services without networks are placed last.
+///
+/// Networks are chained starting from the first declared one, the next network shares the members
+/// with the most recently chained one when possible, so the bridged networks come one after another.
+fn order_by_network_membership(
+    traversal_order: &mut [String],
+    networks: &[DockerNetwork],
+    container_name_to_container_struct: &HashMap<&str, DockerContainer>,
+) {
+    let members = networks
+        .iter()
+        .map(|network| {
+            traversal_order
+                .iter()
+                .filter(|name| {
+                    is_in_network(container_name_to_container_struct, name, &network.name)
+                })
+                .cloned()
+                .collect::<HashSet<String>>()
+        })
+        .collect::<Vec<_>>();
+    let mut chain: Vec<usize> = vec![];
+    while chain.len() < networks.len() {
+        let is_free = |index: &usize| !chain.contains(index);
+        let next = chain
+            .iter()
+            .rev()
+            .find_map(|chained| {
+                (0..networks.len())
+                    .filter(is_free)
+                    .find(|index| !members[*chained].is_disjoint(&members[*index]))
+            })
+            .or_else(|| (0..networks.len()).find(is_free))
+            .unwrap();
+        chain.push(next);
     }
+    traversal_order.sort_by_key(|name| {
+        let network_indexes = chain
+            .iter()
+            .enumerate()
+            .filter(|(_, network)| members[**network].contains(name))
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+        (
+            network_indexes.first().copied().unwrap_or(usize::MAX),
+            network_indexes.last().copied().unwrap_or(usize::MAX),
+        )
+    });
+}
+
+/// Members of each network in the `traversal_order`, a single boundary is drawn around all of them
+fn find_network_members(
+    networks: &[DockerNetwork],
+    traversal_order: &[String],
+    container_name_to_container_struct: &HashMap<&str, DockerContainer>,
+) -> Vec<(DockerNetwork, Vec<String>)> {
+    networks
+        .iter()
+        .filter_map(|network| {
+            let members = traversal_order
+                .iter()
+                .filter(|name| {
+                    is_in_network(container_name_to_container_struct, name, &network.name)
+                })
+                .cloned()
+                .collect::<Vec<String>>();
+            // networks without members are not rendered
+            (!members.is_empty()).then(|| (network.clone(), members))
+        })
+        .collect()
 }
 
 /// Returns `True` if a container with the `container_name` name
@@ -777,45 +1793,17 @@ fn find_containers_in_network(
 /// Otherwise `False`
 fn is_in_network(
     container_name_to_container_struct: &HashMap<&str, DockerContainer>,
-    container_name: String,
-    network_name: String,
+    container_name: &str,
+    network_name: &str,
 ) -> bool {
     container_name_to_container_struct
-        .get(container_name.as_str())
-        .unwrap()
-        .clone()
-        .networks
-        .unwrap()
-        .contains(&network_name)
-}
-
-/// According to current `exc.app_state.grid_size` setting and text/font size
-/// it's possible to accommodate approximately 3 letters in one grid item.
-/// The container width is 7 grid items(140) in total and uses only 5 grid items
-/// to accommodate the text up to 14 characters(`max_container_name_len`)
-/// Empirically found that for
-///  20 | 1.5 letters in grid
-///  28 | 1   letter in grid
-///  36 | 1   letter in grid
-fn find_additional_width(container_name: &str, scale: &i32, font_size: &i32) -> i32 {
-    let container_name_len = container_name.len();
-    let (container_name_len_max, elements_per_item_grid) = match *font_size {
-        FONT_SIZE_SMALL => (14, 3),
-        FONT_SIZE_MEDIUM => (9, 2),
-        FONT_SIZE_LARGE => (5, 1),
-        FONT_SIZE_EXTRA_LARGE => (2, 1),
-        _ => (1, 1),
-    };
-    let text_accommodation_len_default = 5;
-    let text_accommodation_margin = 3;
-    if container_name_len > container_name_len_max {
-        let required_space_for_text = ((container_name_len / elements_per_item_grid)
-            - text_accommodation_len_default
-            + text_accommodation_margin) as i32;
-        scale * required_space_for_text
-    } else {
-        0
-    }
+        .get(container_name)
+        .is_some_and(|container| {
+            container
+                .network_memberships()
+                .iter()
+                .any(|network| network == network_name)
+        })
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -823,12 +1811,25 @@ struct DockerContainer {
     pub id: String,
     image: String,
     command: Option<String>,
-    environment: Option<HashMap<String, String>>,
-    depends_on: Option<Vec<String>>,
-    ports: Option<Vec<String>>, // HOST:CONTAINER
-    volumes: Option<Vec<String>>,
+    environment: Option<IndexMap<String, Option<String>>>,
+    depends_on: Option<Vec<DockerDependency>>,
+    ports: Option<Vec<DockerPort>>,
+    volumes: Option<Vec<DockerVolume>>,
     networks: Option<Vec<String>>, // TODO consider Set
-                                   // TODO: add other fields
+    profiles: Option<Vec<String>>,
+    secrets: Option<Vec<ResourceReference>>,
+    configs: Option<Vec<ResourceReference>>,
+    links: Option<Vec<String>>,
+    volumes_from: Option<Vec<String>>,
+    network_mode: Option<String>,
+    ipc: Option<String>,
+    pid: Option<String>,
+    network_attachments: Vec<DockerNetworkAttachment>,
+    labels: IndexMap<String, String>,
+    healthcheck: bool,
+    deploy: Option<DockerDeploy>,
+    scale: Option<u32>,
+    // TODO: add other fields
 }
 
 impl DockerContainer {
@@ -842,22 +1843,98 @@ impl DockerContainer {
             volumes: None,
             depends_on: None,
             networks: None,
+            profiles: None,
+            secrets: None,
+            configs: None,
+            links: None,
+            volumes_from: None,
+            network_mode: None,
+            ipc: None,
+            pid: None,
+            network_attachments: vec![],
+            labels: IndexMap::new(),
+            healthcheck: false,
+            deploy: None,
+            scale: None,
         }
     }
 
-    fn parse_depends_on(value: &Value) -> Option<Vec<String>> {
+    /// Connections to the other services declared via `depends_on`, `links`, `volumes_from`
+    /// and `network_mode`/`ipc`/`pid` in the `service:<name>` form
+    fn dependency_edges(&self) -> Vec<DependencyEdge> {
+        let mut edges = vec![];
+        for dependency in self.depends_on.iter().flatten() {
+            edges.push(DependencyEdge::depends_on(dependency));
+        }
+        // `SERVICE[:ALIAS]`
+        for link in self.links.iter().flatten() {
+            let (name, alias) = match link.split_once(':') {
+                Some((name, alias)) => (name, Some(alias.to_string())),
+                None => (link.as_str(), None),
+            };
+            edges.push(DependencyEdge::new(
+                name.to_string(),
+                EdgeKind::Links,
+                alias,
+            ));
+        }
+        // `[service:]SERVICE[:MODE]` or `container:CONTAINER[:MODE]`, the latter is skipped
+        for volumes_from in self.volumes_from.iter().flatten() {
+            let volumes_from = volumes_from
+                .strip_prefix("service:")
+                .unwrap_or(volumes_from);
+            if volumes_from.starts_with("container:") {
+                continue;
+            }
+            let (name, mode) = match volumes_from.split_once(':') {
+                Some((name, mode)) => (name, Some(mode.to_string())),
+                None => (volumes_from, None),
+            };
+            edges.push(DependencyEdge::new(
+                name.to_string(),
+                EdgeKind::VolumesFrom,
+                mode,
+            ));
+        }
+        for (kind, mode) in [
+            (EdgeKind::NetworkMode, &self.network_mode),
+            (EdgeKind::Ipc, &self.ipc),
+            (EdgeKind::Pid, &self.pid),
+        ] {
+            if let Some(name) = mode.as_deref().and_then(|m| m.strip_prefix("service:")) {
+                edges.push(DependencyEdge::new(name.to_string(), kind, None));
+            }
+        }
+        edges
+    }
+
+    /// Services without `profiles` are always enabled,
+    /// otherwise at least one of the service profiles should be active (`*` enables all profiles)
+    fn is_enabled(&self, active_profiles: &[String]) -> bool {
+        match &self.profiles {
+            Some(profiles) if !profiles.is_empty() => active_profiles
+                .iter()
+                .any(|active| active == "*" || profiles.contains(active)),
+            _ => true,
+        }
+    }
+
+    fn parse_depends_on(value: &Value) -> Option<Vec<DockerDependency>> {
         match value {
             Value::Sequence(depends_on) => {
-                let depends_on_vec: Vec<String> = depends_on
+                let depends_on_vec: Vec<DockerDependency> = depends_on
                     .iter()
-                    .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                    .filter_map(|item| item.as_str().map(DockerDependency::new))
                     .collect();
                 Some(depends_on_vec)
             }
             Value::Mapping(depends_on) => {
-                let depends_on_vec: Vec<String> = depends_on
-                    .keys()
-                    .filter_map(|key| key.as_str().map(|s| s.to_string()))
+                let depends_on_vec: Vec<DockerDependency> = depends_on
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        key.as_str()
+                            .map(|service| DockerDependency::parse_long_syntax(service, value))
+                    })
                     .collect();
                 Some(depends_on_vec)
             }
@@ -865,6 +1942,64 @@ impl DockerContainer {
         }
     }
 
+    /// `labels` could be declared either as a mapping or as a list of `key=value` items
+    fn parse_labels(value: &Value) -> IndexMap<String, String> {
+        match value {
+            Value::Mapping(labels) => labels
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((
+                        scalar_to_string(key)?,
+                        scalar_to_string(value).unwrap_or_default(),
+                    ))
+                })
+                .collect(),
+            Value::Sequence(labels) => labels
+                .iter()
+                .filter_map(scalar_to_string)
+                .map(|label| match label.split_once('=') {
+                    Some((key, value)) => (key.to_string(), value.to_string()),
+                    None => (label, String::new()),
+                })
+                .collect(),
+            _ => IndexMap::new(),
+        }
+    }
+
+    /// `healthcheck` is considered defined unless it is disabled
+    /// either via `disable: true` or via `test: ["NONE"]`
+    fn parse_healthcheck(value: &Value) -> bool {
+        let disabled = value.get("disable").and_then(Value::as_bool) == Some(true)
+            || value
+                .get("test")
+                .and_then(|test| test.get(0).or(Some(test)))
+                .and_then(Value::as_str)
+                == Some("NONE");
+        value.is_mapping() && !disabled
+    }
+
+    fn parse_ports(value: &Value) -> Option<Vec<DockerPort>> {
+        match value {
+            Value::Sequence(ports) => {
+                let ports_vec: Vec<DockerPort> =
+                    ports.iter().filter_map(DockerPort::parse).collect();
+                Some(ports_vec)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_volumes(value: &Value) -> Option<Vec<DockerVolume>> {
+        match value {
+            Value::Sequence(volumes) => {
+                let volumes_vec: Vec<DockerVolume> =
+                    volumes.iter().filter_map(DockerVolume::parse).collect();
+                Some(volumes_vec)
+            }
+            _ => None,
+        }
+    }
+
     fn parse_networks(value: &Value) -> Option<Vec<String>> {
         match value {
             Value::Sequence(networks) => {
@@ -885,9 +2020,60 @@ impl DockerContainer {
         }
     }
 
-    fn convert_to_container(id: String, value: &Value) -> Self {
+    /// `env_file` could be declared as a single path, a list of paths
+    /// or a list of `path`/`required` mappings
+    fn parse_env_files(value: &Value) -> Option<Vec<(String, bool)>> {
+        match value {
+            Value::String(path) => Some(vec![(path.clone(), true)]),
+            Value::Sequence(env_files) => {
+                let env_files_vec: Vec<(String, bool)> = env_files
+                    .iter()
+                    .filter_map(|env_file| match env_file {
+                        Value::String(path) => Some((path.clone(), true)),
+                        Value::Mapping(env_file) => {
+                            env_file.get("path").and_then(|p| p.as_str()).map(|path| {
+                                let required = env_file
+                                    .get("required")
+                                    .and_then(|r| r.as_bool())
+                                    .unwrap_or(true);
+                                (path.to_string(), required)
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                Some(env_files_vec)
+            }
+            _ => None,
+        }
+    }
+
+    /// Loads variables from the `env_files`, paths are relative to the docker-compose file.
+    /// Variables declared later override the ones declared earlier.
+    /// A missing env file fails the conversion unless it's marked `required: false`
+    fn load_env_files(
+        compose_file_path: &str,
+        env_files: &[(String, bool)],
+    ) -> IndexMap<String, Option<String>> {
+        let mut env = IndexMap::new();
+        for (env_file, required) in env_files {
+            let env_file_path = file_utils::resolve_relative_path(compose_file_path, env_file);
+            match file_utils::get_env_file_content(&env_file_path) {
+                Ok(content) => env.extend(env_utils::parse_env_file_content(&content)),
+                Err(err) if *required => {
+                    println!("{}", err);
+                    exit(1);
+                }
+                Err(_) => (),
+            }
+        }
+        env
+    }
+
+    fn convert_to_container(id: String, value: &Value, compose_file_path: &str) -> Self {
         let mapping = value.as_mapping().unwrap();
         let mut container = DockerContainer::new(id);
+        let mut env_files = Vec::new();
         for (key, value) in mapping {
             match key.as_str().unwrap() {
                 "image" => {
@@ -901,33 +2087,23 @@ impl DockerContainer {
                     }
                 }
                 "environment" => {
-                    if let Value::Mapping(environment) = value {
-                        let mut env_map = HashMap::new();
-                        for (env_key, env_value) in environment {
-                            if let (Value::String(key), Value::String(value)) = (env_key, env_value)
-                            {
-                                env_map.insert(key.clone(), value.clone());
-                            }
-                        }
-                        container.environment = Some(env_map);
+                    if let Some(environment) = env_utils::parse_environment(value) {
+                        container.environment = Some(environment);
+                    }
+                }
+                "env_file" => {
+                    if let Some(files) = Self::parse_env_files(value) {
+                        env_files = files;
                     }
                 }
                 "ports" => {
-                    if let Value::Sequence(ports) = value {
-                        let port_strings = ports
-                            .iter()
-                            .filter_map(|port| port.as_str().map(|p| p.to_string()))
-                            .collect();
-                        container.ports = Some(port_strings);
+                    if let Some(ports) = Self::parse_ports(value) {
+                        container.ports = Some(ports);
                     }
                 }
                 "volumes" => {
-                    if let Value::Sequence(volumes) = value {
-                        let volume_strings = volumes
-                            .iter()
-                            .filter_map(|volume| volume.as_str().map(|v| v.to_string()))
-                            .collect();
-                        container.volumes = Some(volume_strings);
+                    if let Some(volumes) = Self::parse_volumes(value) {
+                        container.volumes = Some(volumes);
                     }
                 }
                 "depends_on" => {
@@ -939,13 +2115,782 @@ impl DockerContainer {
                     if let Some(networks) = Self::parse_networks(value) {
                         container.networks = Some(networks);
                     }
+                    container.network_attachments = DockerNetworkAttachment::parse_networks(value);
+                }
+                "secrets" => {
+                    if let Some(secrets) = ResourceReference::parse_sequence(value) {
+                        container.secrets = Some(secrets);
+                    }
+                }
+                "configs" => {
+                    if let Some(configs) = ResourceReference::parse_sequence(value) {
+                        container.configs = Some(configs);
+                    }
+                }
+                "deploy" => {
+                    container.deploy = Some(DockerDeploy::parse(value));
+                }
+                "scale" => {
+                    container.scale = scalar_to_string(value).and_then(|s| s.parse().ok());
+                }
+                "labels" => {
+                    container.labels = Self::parse_labels(value);
+                }
+                "healthcheck" => {
+                    container.healthcheck = Self::parse_healthcheck(value);
+                }
+                "links" => {
+                    if let Value::Sequence(links) = value {
+                        let link_strings = links
+                            .iter()
+                            .filter_map(|link| link.as_str().map(|l| l.to_string()))
+                            .collect();
+                        container.links = Some(link_strings);
+                    }
+                }
+                "volumes_from" => {
+                    if let Value::Sequence(volumes_from) = value {
+                        let volumes_from_strings = volumes_from
+                            .iter()
+                            .filter_map(|volume| volume.as_str().map(|v| v.to_string()))
+                            .collect();
+                        container.volumes_from = Some(volumes_from_strings);
+                    }
+                }
+                "network_mode" => {
+                    if let Value::String(network_mode) = value {
+                        container.network_mode = Some(network_mode.clone());
+                    }
+                }
+                "ipc" => {
+                    if let Value::String(ipc) = value {
+                        container.ipc = Some(ipc.clone());
+                    }
+                }
+                "pid" => {
+                    if let Value::String(pid) = value {
+                        container.pid = Some(pid.clone());
+                    }
+                }
+                "profiles" => {
+                    if let Value::Sequence(profiles) = value {
+                        let profile_strings = profiles
+                            .iter()
+                            .filter_map(|profile| profile.as_str().map(|p| p.to_string()))
+                            .collect();
+                        container.profiles = Some(profile_strings);
+                    }
                 }
                 // TODO: Handle other fields
                 _ => (),
             }
         }
+        if !env_files.is_empty() {
+            // `environment` takes precedence over `env_file`
+            let mut environment = Self::load_env_files(compose_file_path, &env_files);
+            environment.extend(container.environment.unwrap_or_default());
+            container.environment = Some(environment);
+        }
         container
     }
+
+    /// Networks the service is attached to.
+    /// Services without `networks` are attached to the `default` network,
+    /// `network_mode: host`/`none` are represented by the `host`/`none` pseudo networks
+    /// and the services sharing the network stack of another service/container are not attached at all.
+    fn network_memberships(&self) -> Vec<String> {
+        match self.network_mode.as_deref() {
+            Some(mode @ ("host" | "none")) => vec![mode.to_string()],
+            Some(_) => vec![],
+            None => self
+                .networks
+                .clone()
+                .unwrap_or_else(|| vec![DEFAULT_NETWORK.to_string()]),
+        }
+    }
+
+    /// Static addresses and aliases of the service in each network,
+    /// f.e. `backend: 172.16.238.10 (aliases: db)`
+    fn network_address_lines(&self) -> Vec<String> {
+        self.network_attachments
+            .iter()
+            .map(DockerNetworkAttachment::describe)
+            .collect()
+    }
+
+    /// Badge of the replicated service: `×N` when more than one replica is requested
+    /// via `deploy.replicas` or `scale`, `global` for the global deploy mode
+    fn replicas_badge(&self) -> Option<String> {
+        let deploy = self.deploy.as_ref();
+        if deploy.and_then(|d| d.mode.as_deref()) == Some("global") {
+            return Some("global".to_string());
+        }
+        deploy
+            .and_then(|d| d.replicas)
+            .or(self.scale)
+            .filter(|replicas| *replicas > 1)
+            .map(|replicas| format!("×{}", replicas))
+    }
+
+    /// Resource limits and reservations in `limits: 0.5 cpus, 512M | reservations: 128M` format
+    fn resources_line(&self) -> Option<String> {
+        let deploy = self.deploy.as_ref()?;
+        let line = [
+            ("limits", &deploy.limits),
+            ("reservations", &deploy.reservations),
+        ]
+        .iter()
+        .filter_map(|(name, resources)| {
+            resources
+                .as_ref()
+                .and_then(DockerResources::describe)
+                .map(|description| format!("{}: {}", name, description))
+        })
+        .collect::<Vec<String>>()
+        .join(" | ");
+        (!line.is_empty()).then_some(line)
+    }
+
+    /// Placement constraints in `placement: node.role == manager` format
+    fn placement_lines(&self) -> Vec<String> {
+        self.deploy
+            .iter()
+            .flat_map(|deploy| &deploy.placement_constraints)
+            .map(|constraint| format!("placement: {}", constraint))
+            .collect()
+    }
+
+    /// Environment variables in `KEY=value` format, values are masked according to the `redacted_keys`
+    fn environment_lines(&self, redacted_keys: &[String]) -> Vec<String> {
+        self.environment
+            .iter()
+            .flatten()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{}={}", key, env_utils::redact(key, value, redacted_keys)),
+                None => key.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The `deploy` attribute of a service.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+struct DockerDeploy {
+    mode: Option<String>,
+    replicas: Option<u32>,
+    limits: Option<DockerResources>,
+    reservations: Option<DockerResources>,
+    placement_constraints: Vec<String>,
+}
+
+impl DockerDeploy {
+    fn parse(value: &Value) -> Self {
+        let resources = value.get("resources");
+        Self {
+            mode: value.get("mode").and_then(scalar_to_string),
+            replicas: value
+                .get("replicas")
+                .and_then(scalar_to_string)
+                .and_then(|replicas| replicas.parse().ok()),
+            limits: resources
+                .and_then(|r| r.get("limits"))
+                .map(DockerResources::parse),
+            reservations: resources
+                .and_then(|r| r.get("reservations"))
+                .map(DockerResources::parse),
+            placement_constraints: value
+                .get("placement")
+                .and_then(|placement| placement.get("constraints"))
+                .and_then(Value::as_sequence)
+                .map(|constraints| constraints.iter().filter_map(scalar_to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// `cpus` and `memory` of `deploy.resources.limits` or `deploy.resources.reservations`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct DockerResources {
+    cpus: Option<String>,
+    memory: Option<String>,
+}
+
+impl DockerResources {
+    fn parse(value: &Value) -> Self {
+        Self {
+            cpus: value.get("cpus").and_then(scalar_to_string),
+            memory: value.get("memory").and_then(scalar_to_string),
+        }
+    }
+
+    fn describe(&self) -> Option<String> {
+        let description = self
+            .cpus
+            .iter()
+            .map(|cpus| format!("{} cpus", cpus))
+            .chain(self.memory.clone())
+            .collect::<Vec<String>>()
+            .join(", ");
+        (!description.is_empty()).then_some(description)
+    }
+}
+
+const DEFAULT_DEPENDS_ON_CONDITION: &str = "service_started";
+
+/// Entry of the `depends_on` attribute.
+/// The short syntax only declares the `service`, the rest of the fields get the default values
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct DockerDependency {
+    service: String,
+    condition: String,
+    restart: bool,
+    required: bool,
+}
+
+impl DockerDependency {
+    fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            condition: DEFAULT_DEPENDS_ON_CONDITION.to_string(),
+            restart: false,
+            required: true,
+        }
+    }
+
+    /// `db: { condition: service_healthy, restart: true, required: false }`
+    fn parse_long_syntax(service: &str, value: &Value) -> Self {
+        let mut dependency = Self::new(service);
+        if let Some(condition) = value.get("condition").and_then(Value::as_str) {
+            dependency.condition = condition.to_string();
+        }
+        if let Some(restart) = value.get("restart").and_then(Value::as_bool) {
+            dependency.restart = restart;
+        }
+        if let Some(required) = value.get("required").and_then(Value::as_bool) {
+            dependency.required = required;
+        }
+        dependency
+    }
+}
+
+/// Port exposed by a service.
+/// Both the short syntax (`"8080"`, `8080`, `"127.0.0.1:8080:80/udp"`)
+/// and the long syntax (`target`, `published`, `host_ip`, `protocol`, `mode`) are supported.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct DockerPort {
+    target: String,
+    published: Option<String>,
+    host_ip: Option<String>,
+    protocol: Option<String>,
+    mode: Option<String>,
+}
+
+impl DockerPort {
+    fn new(target: String) -> Self {
+        Self {
+            target,
+            published: None,
+            host_ip: None,
+            protocol: None,
+            mode: None,
+        }
+    }
+
+    fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(port) => Self::parse_short_syntax(port),
+            Value::Number(port) => Some(Self::new(port.to_string())),
+            Value::Mapping(port) => Self::parse_long_syntax(port),
+            _ => None,
+        }
+    }
+
+    /// `[[HOST_IP:]HOST_PORT:]CONTAINER_PORT[/PROTOCOL]`
+    fn parse_short_syntax(port: &str) -> Option<Self> {
+        let port = port.trim();
+        if port.is_empty() {
+            return None;
+        }
+        let (ports, protocol) = match port.rsplit_once('/') {
+            Some((ports, protocol)) => (ports, Some(protocol.to_string())),
+            None => (port, None),
+        };
+        let (host, target) = match ports.rsplit_once(':') {
+            Some((host, target)) => (Some(host), target),
+            None => (None, ports),
+        };
+        let (host_ip, published) = match host {
+            Some(host) => match host.rsplit_once(':') {
+                Some((host_ip, published)) => (Some(host_ip), published),
+                None => (None, host),
+            },
+            None => (None, ""),
+        };
+        Some(Self {
+            target: target.to_string(),
+            published: Some(published)
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string()),
+            host_ip: host_ip.map(|ip| ip.to_string()),
+            protocol,
+            mode: None,
+        })
+    }
+
+    fn parse_long_syntax(port: &Mapping) -> Option<Self> {
+        let target = port.get("target").and_then(scalar_to_string)?;
+        Some(Self {
+            target,
+            published: port.get("published").and_then(scalar_to_string),
+            host_ip: port.get("host_ip").and_then(scalar_to_string),
+            protocol: port.get("protocol").and_then(scalar_to_string),
+            mode: port.get("mode").and_then(scalar_to_string),
+        })
+    }
+}
+
+/// Volume mounted by a service.
+/// Both the short syntax (`"data:/var/lib/data:ro"`, `"./config:/etc/config"`, `"/tmp"`)
+/// and the long syntax (`type`, `source`, `target`, `read_only`) are supported.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct DockerVolume {
+    volume_type: String,
+    source: Option<String>,
+    target: String,
+    read_only: bool,
+}
+
+impl DockerVolume {
+    fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(volume) => Self::parse_short_syntax(volume),
+            Value::Mapping(volume) => Self::parse_long_syntax(volume),
+            _ => None,
+        }
+    }
+
+    /// `[SOURCE:]TARGET[:MODE]`, where `SOURCE` is either a volume name or a host path
+    fn parse_short_syntax(volume: &str) -> Option<Self> {
+        let parts: Vec<&str> = volume.split(':').collect();
+        let (source, target, mode) = match parts.as_slice() {
+            [target] => (None, *target, None),
+            [source, target] => (Some(*source), *target, None),
+            [source, target, mode, ..] => (Some(*source), *target, Some(*mode)),
+            [] => return None,
+        };
+        if target.is_empty() {
+            return None;
+        }
+        let volume_type = match source {
+            Some(source) if Self::is_host_path(source) => "bind",
+            _ => "volume",
+        };
+        Some(Self {
+            volume_type: volume_type.to_string(),
+            source: source.map(|s| s.to_string()),
+            target: target.to_string(),
+            read_only: mode.is_some_and(|mode| mode.split(',').any(|option| option == "ro")),
+        })
+    }
+
+    fn parse_long_syntax(volume: &Mapping) -> Option<Self> {
+        let target = volume.get("target").and_then(scalar_to_string)?;
+        let source = volume.get("source").and_then(scalar_to_string);
+        let volume_type = volume
+            .get("type")
+            .and_then(scalar_to_string)
+            .unwrap_or_else(|| match &source {
+                Some(source) if Self::is_host_path(source) => "bind".to_string(),
+                _ => "volume".to_string(),
+            });
+        Some(Self {
+            volume_type,
+            source,
+            target,
+            read_only: volume
+                .get("read_only")
+                .and_then(|read_only| read_only.as_bool())
+                .unwrap_or(false),
+        })
+    }
+
+    fn is_host_path(source: &str) -> bool {
+        source.starts_with('.') || source.starts_with('/') || source.starts_with('~')
+    }
+
+    /// Name of the named volume, `None` for bind mounts, tmpfs and anonymous volumes
+    fn volume_name(&self) -> Option<&str> {
+        match self.volume_type.as_str() {
+            "volume" => self.source.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Host path of the bind mount, `None` for other volume types
+    fn host_path(&self) -> Option<&str> {
+        match self.volume_type.as_str() {
+            "bind" => self.source.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Mount path along with the access mode, f.e. `/var/lib/data:ro`
+    fn mount_label(&self) -> String {
+        format!(
+            "{}:{}",
+            self.target,
+            if self.read_only { "ro" } else { "rw" }
+        )
+    }
+}
+
+const DEFAULT_NETWORK: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NetworkKind {
+    Declared,
+    External,
+    /// `default` network, which is created by Compose implicitly unless declared
+    Default,
+    /// `network_mode: host`
+    Host,
+    /// `network_mode: none`
+    None,
+}
+
+/// Network rendered as a boundary around its members.
+/// `project` is used to label the `default` network the way Compose names it.
+#[derive(Debug, Clone, PartialEq)]
+struct DockerNetwork {
+    name: String,
+    kind: NetworkKind,
+    project: String,
+    driver: Option<String>,
+    internal: bool,
+    ipam: Vec<DockerIpamConfig>,
+}
+
+impl DockerNetwork {
+    fn new(name: &str, kind: NetworkKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            project: String::new(),
+            driver: None,
+            internal: false,
+            ipam: vec![],
+        }
+    }
+
+    /// Driver and `internal` flag line followed by the IPAM configuration lines
+    fn details(&self) -> Vec<String> {
+        let driver_line = self
+            .driver
+            .iter()
+            .map(|driver| format!("driver: {}", driver))
+            .chain(self.internal.then(|| "internal".to_string()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        (!driver_line.is_empty())
+            .then_some(driver_line)
+            .into_iter()
+            .chain(self.ipam.iter().map(DockerIpamConfig::describe))
+            .collect()
+    }
+
+    fn label(&self) -> String {
+        match self.kind {
+            NetworkKind::External => format!("{} (external)", self.name),
+            NetworkKind::Default => format!("{}_{}", self.project, self.name),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Entry of the network `ipam.config` section
+#[derive(Debug, Clone, PartialEq)]
+struct DockerIpamConfig {
+    subnet: Option<String>,
+    gateway: Option<String>,
+    ip_range: Option<String>,
+}
+
+impl DockerIpamConfig {
+    fn parse(value: &Value) -> Self {
+        Self {
+            subnet: value.get("subnet").and_then(scalar_to_string),
+            gateway: value.get("gateway").and_then(scalar_to_string),
+            ip_range: value.get("ip_range").and_then(scalar_to_string),
+        }
+    }
+
+    /// `subnet: 172.28.0.0/16, gateway: 172.28.0.1`
+    fn describe(&self) -> String {
+        [
+            ("subnet", &self.subnet),
+            ("ip_range", &self.ip_range),
+            ("gateway", &self.gateway),
+        ]
+        .iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("{}: {}", name, value)))
+        .collect::<Vec<String>>()
+        .join(", ")
+    }
+}
+
+/// Service specific settings of the network membership (long `networks` syntax of a service)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct DockerNetworkAttachment {
+    network: String,
+    aliases: Vec<String>,
+    ipv4_address: Option<String>,
+    ipv6_address: Option<String>,
+}
+
+impl DockerNetworkAttachment {
+    /// Only the memberships declaring aliases or static addresses are returned
+    fn parse_networks(value: &Value) -> Vec<Self> {
+        match value {
+            Value::Mapping(networks) => networks
+                .iter()
+                .filter_map(|(name, settings)| {
+                    let attachment = Self {
+                        network: name.as_str()?.to_string(),
+                        aliases: settings
+                            .get("aliases")
+                            .and_then(Value::as_sequence)
+                            .map(|aliases| aliases.iter().filter_map(scalar_to_string).collect())
+                            .unwrap_or_default(),
+                        ipv4_address: settings.get("ipv4_address").and_then(scalar_to_string),
+                        ipv6_address: settings.get("ipv6_address").and_then(scalar_to_string),
+                    };
+                    let has_settings = !attachment.aliases.is_empty()
+                        || attachment.ipv4_address.is_some()
+                        || attachment.ipv6_address.is_some();
+                    has_settings.then_some(attachment)
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// `backend: 172.16.238.10, 2001:3984:3989::10 (aliases: db, database)`
+    fn describe(&self) -> String {
+        let addresses = self
+            .ipv4_address
+            .iter()
+            .chain(self.ipv6_address.iter())
+            .cloned()
+            .collect::<Vec<String>>();
+        let mut line = self.network.clone();
+        if !addresses.is_empty() {
+            line = format!("{}: {}", line, addresses.join(", "));
+        }
+        if !self.aliases.is_empty() {
+            line = format!("{} (aliases: {})", line, self.aliases.join(", "));
+        }
+        line
+    }
+}
+
+/// Networks declared in the top-level `networks` section,
+/// both `external: true` and the legacy `external: { name: ... }` forms are recognized
+fn parse_top_level_networks(value: &Value) -> Vec<DockerNetwork> {
+    match value {
+        Value::Mapping(networks) => networks
+            .iter()
+            .filter_map(|(name, network)| {
+                let name = name.as_str()?;
+                let external = match network.get("external") {
+                    Some(Value::Bool(external)) => *external,
+                    Some(Value::Mapping(_)) => true,
+                    _ => false,
+                };
+                let kind = if external {
+                    NetworkKind::External
+                } else if name == DEFAULT_NETWORK {
+                    NetworkKind::Default
+                } else {
+                    NetworkKind::Declared
+                };
+                let mut docker_network = DockerNetwork::new(name, kind);
+                docker_network.driver = network.get("driver").and_then(scalar_to_string);
+                docker_network.internal =
+                    network.get("internal").and_then(Value::as_bool) == Some(true);
+                docker_network.ipam = network
+                    .get("ipam")
+                    .and_then(|ipam| ipam.get("config"))
+                    .and_then(Value::as_sequence)
+                    .map(|config| config.iter().map(DockerIpamConfig::parse).collect())
+                    .unwrap_or_default();
+                Some(docker_network)
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// All the networks the `containers` are attached to: declared networks go first
+/// followed by the implicit `default` network (only if `default_network` is enabled),
+/// networks referenced without declaration and `host`/`none` pseudo networks.
+/// Networks without members are kept, they are skipped during rendering.
+fn collect_networks<'a>(
+    declared_networks: Vec<DockerNetwork>,
+    containers: impl Iterator<Item = &'a DockerContainer>,
+    project: &str,
+    default_network: bool,
+) -> Vec<DockerNetwork> {
+    let mut networks = declared_networks;
+    for membership in containers.flat_map(|container| container.network_memberships()) {
+        if networks.iter().any(|network| network.name == membership) {
+            continue;
+        }
+        let kind = match membership.as_str() {
+            DEFAULT_NETWORK if !default_network => continue,
+            DEFAULT_NETWORK => NetworkKind::Default,
+            "host" => NetworkKind::Host,
+            "none" => NetworkKind::None,
+            _ => NetworkKind::Declared,
+        };
+        networks.push(DockerNetwork::new(&membership, kind));
+    }
+    for network in networks.iter_mut() {
+        network.project = project.to_string();
+    }
+    networks
+}
+
+/// Compose project name: `COMPOSE_PROJECT_NAME` variable, top-level `name`
+/// or the name of the directory containing the (first) docker-compose file
+fn project_name(
+    compose: &Mapping,
+    compose_file_path: &str,
+    variables: &HashMap<String, String>,
+) -> String {
+    let name = variables
+        .get("COMPOSE_PROJECT_NAME")
+        .cloned()
+        .or_else(|| compose.get("name").and_then(scalar_to_string))
+        .unwrap_or_else(|| {
+            let path = Path::new(compose_file_path);
+            path.parent()
+                .and_then(|parent| {
+                    if parent.as_os_str().is_empty() {
+                        env::current_dir().ok()
+                    } else {
+                        parent.canonicalize().ok().or(Some(parent.to_path_buf()))
+                    }
+                })
+                .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_default()
+        });
+    // Compose keeps only lowercase letters, digits, dashes and underscores
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// Named volumes declared in the top-level `volumes` section
+/// along with the `external` flag
+fn parse_top_level_volumes(value: &Value) -> IndexMap<String, bool> {
+    match value {
+        Value::Mapping(volumes) => volumes
+            .iter()
+            .filter_map(|(name, volume)| {
+                let external = volume
+                    .get("external")
+                    .and_then(|external| external.as_bool())
+                    .unwrap_or(false);
+                name.as_str().map(|name| (name.to_string(), external))
+            })
+            .collect(),
+        _ => IndexMap::new(),
+    }
+}
+
+/// Secret or config granted to a service.
+/// Both the short syntax (`- db_password`) and the long syntax (`source`, `target`, etc) are supported.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct ResourceReference {
+    source: String,
+    target: Option<String>,
+}
+
+impl ResourceReference {
+    fn parse_sequence(value: &Value) -> Option<Vec<Self>> {
+        match value {
+            Value::Sequence(references) => {
+                let references_vec: Vec<Self> = references.iter().filter_map(Self::parse).collect();
+                Some(references_vec)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(source) => Some(Self {
+                source: source.clone(),
+                target: None,
+            }),
+            Value::Mapping(reference) => Some(Self {
+                source: reference.get("source").and_then(scalar_to_string)?,
+                target: reference.get("target").and_then(scalar_to_string),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Path inside the container, secrets are mounted to `/run/secrets/<source>`
+    /// and configs to `/<source>` unless `target` is provided
+    fn target_path(&self, kind: &str) -> String {
+        match (&self.target, kind) {
+            (Some(target), "secret") if !target.starts_with('/') => {
+                format!("/run/secrets/{}", target)
+            }
+            (Some(target), _) => target.clone(),
+            (None, "secret") => format!("/run/secrets/{}", self.source),
+            (None, _) => format!("/{}", self.source),
+        }
+    }
+}
+
+/// Secrets/configs declared in the top-level section along with their origin
+/// (`file`, `environment`, `content` or `external`)
+fn parse_top_level_resources(value: &Value) -> IndexMap<String, Option<String>> {
+    match value {
+        Value::Mapping(resources) => resources
+            .iter()
+            .filter_map(|(name, resource)| {
+                let origin = if let Some(file) = resource.get("file").and_then(scalar_to_string) {
+                    Some(format!("file: {}", file))
+                } else if let Some(variable) =
+                    resource.get("environment").and_then(scalar_to_string)
+                {
+                    Some(format!("env: {}", variable))
+                } else if resource.get("content").is_some() {
+                    Some("content".to_string())
+                } else if resource.get("external").and_then(|e| e.as_bool()) == Some(true) {
+                    Some("external".to_string())
+                } else {
+                    None
+                };
+                name.as_str().map(|name| (name.to_string(), origin))
+            })
+            .collect(),
+        _ => IndexMap::new(),
+    }
+}
+
+/// Converts a scalar yaml value (string, number, bool) into a String
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
 }
 
 // fn parse_depends_on(value: Value) -> Option<Vec<String>> {
@@ -988,48 +2933,591 @@ impl DockerContainer {
 //     }
 // }
 
-fn generate_id() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
 // #[test]
 // fn check_parsing() {
 //
 // }
 
+/// Service `name` parsed from its yaml definition
+#[cfg(test)]
+fn test_container(name: &str, yaml: &str) -> DockerContainer {
+    DockerContainer::convert_to_container(
+        name.to_string(),
+        &serde_yaml::from_str(yaml).unwrap(),
+        "docker-compose.yaml",
+    )
+}
+
 #[test]
 fn test_check_port_parsing() {
+    let ports = |port: &str| {
+        extract_host_container_ports(&DockerPort::parse(&Value::String(port.to_string())).unwrap())
+    };
     // - "3000"                 # container port (3000), assigned to random host port
-    let (host_port, container_port) = extract_host_container_ports("3000");
+    let (host_port, container_port) = ports("3000");
     assert_eq!(host_port, "3000");
     assert_eq!(container_port, "3000");
 
     // - "3001-3005"            # container port range (3001-3005), assigned to random host ports
-    let (host_port, container_port) = extract_host_container_ports("3001-3005");
+    let (host_port, container_port) = ports("3001-3005");
     assert_eq!(host_port, "3001-3005");
     assert_eq!(container_port, "3001-3005");
 
     // - "8001:8001"            # container port (8001), assigned to given host port (8001)
-    let (host_port, container_port) = extract_host_container_ports("8001:8001");
+    let (host_port, container_port) = ports("8001:8001");
     assert_eq!(host_port, "8001");
     assert_eq!(container_port, "8001");
 
     // - "9090-9091:8080-8081"  # container port range (8080-8081), assigned to given host port range (9090-9091)
-    let (host_port, container_port) = extract_host_container_ports("9090-9091:8080-8081");
+    let (host_port, container_port) = ports("9090-9091:8080-8081");
     assert_eq!(host_port, "9090-9091");
     assert_eq!(container_port, "8080-8081");
 
     // - "127.0.0.1:8002:8002"  # container port (8002), assigned to given host port (8002) and bind to 127.0.0.1
-    let (host_port, container_port) = extract_host_container_ports("127.0.0.1:8002:8002");
+    let (host_port, container_port) = ports("127.0.0.1:8002:8002");
     assert_eq!(host_port, "127.0.0.1:8002");
     assert_eq!(container_port, "8002");
 
     // - "6060:6060/udp"        # container port (6060) restricted to UDP protocol, assigned to given host (6060)
-    let (host_port, container_port) = extract_host_container_ports("6060:6060/udp");
+    let (host_port, container_port) = ports("6060:6060/udp");
     assert_eq!(host_port, "6060");
     assert_eq!(container_port, "6060/udp");
+
+    // - "[::1]:6001:6001"      # container port (6001), assigned to given host port (6001) and bind to ::1
+    let (host_port, container_port) = ports("[::1]:6001:6001");
+    assert_eq!(host_port, "[::1]:6001");
+    assert_eq!(container_port, "6001");
+
+    // - "127.0.0.1::5000/tcp"  # container port (5000) restricted to TCP, assigned to random host port bound to 127.0.0.1
+    let (host_port, container_port) = ports("127.0.0.1::5000/tcp");
+    assert_eq!(host_port, "127.0.0.1");
+    assert_eq!(container_port, "5000/tcp");
+}
+
+#[test]
+fn test_check_port_parsing_integer_and_long_syntax() {
+    // - 8080                   # container port (8080) declared as integer
+    let port = DockerPort::parse(&serde_yaml::from_str("8080").unwrap()).unwrap();
+    assert_eq!(port, DockerPort::new("8080".to_string()));
+
+    // - target: 80
+    //   published: "8080"
+    //   host_ip: 127.0.0.1
+    //   protocol: udp
+    //   mode: host
+    let port = DockerPort::parse(
+        &serde_yaml::from_str(
+            "{target: 80, published: '8080', host_ip: 127.0.0.1, protocol: udp, mode: host}",
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(port.mode, Some("host".to_string()));
+    let (host_port, container_port) = extract_host_container_ports(&port);
+    assert_eq!(host_port, "127.0.0.1:8080");
+    assert_eq!(container_port, "80/udp");
+
+    // - target: 9000           # only target is mandatory
+    let port = DockerPort::parse(&serde_yaml::from_str("{target: 9000}").unwrap()).unwrap();
+    assert_eq!(
+        extract_host_container_ports(&port),
+        ("9000".to_string(), "9000".to_string())
+    );
+
+    // - published: 9000        # target is missing, the port is ignored
+    assert_eq!(
+        DockerPort::parse(&serde_yaml::from_str("{published: 9000}").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn test_profiles() {
+    let service = |yaml: &str| test_container("container_1", yaml);
+    let always_enabled = service("{image: postgres}");
+    let debug = service("{image: adminer, profiles: [debug, tools]}");
+    let profiles = |profiles: &[&str]| -> Vec<String> {
+        profiles.iter().map(|profile| profile.to_string()).collect()
+    };
+    assert!(always_enabled.is_enabled(&profiles(&[])));
+    assert!(always_enabled.is_enabled(&profiles(&["debug"])));
+    assert!(!debug.is_enabled(&profiles(&[])));
+    assert!(!debug.is_enabled(&profiles(&["prod"])));
+    assert!(debug.is_enabled(&profiles(&["tools"])));
+    assert!(debug.is_enabled(&profiles(&["*"])));
+}
+
+#[test]
+fn test_volume_parsing() {
+    let volume = |yaml: &str| DockerVolume::parse(&serde_yaml::from_str(yaml).unwrap()).unwrap();
+
+    let named = volume("data:/var/lib/data:ro");
+    assert_eq!(named.volume_name(), Some("data"));
+    assert_eq!(named.host_path(), None);
+    assert_eq!(named.mount_label(), "/var/lib/data:ro");
+
+    let bind = volume("./zoo/data:/var/lib/zookeeper/data");
+    assert_eq!(bind.volume_name(), None);
+    assert_eq!(bind.host_path(), Some("./zoo/data"));
+    assert_eq!(bind.mount_label(), "/var/lib/zookeeper/data:rw");
+
+    let anonymous = volume("/var/lib/cache");
+    assert_eq!(anonymous.volume_name(), None);
+    assert_eq!(anonymous.host_path(), None);
+
+    let long_named = volume("{type: volume, source: db-data, target: /data, read_only: true}");
+    assert_eq!(long_named.volume_name(), Some("db-data"));
+    assert_eq!(long_named.mount_label(), "/data:ro");
+
+    let long_bind =
+        volume("{type: bind, source: /var/run/docker.sock, target: /var/run/docker.sock}");
+    assert_eq!(long_bind.host_path(), Some("/var/run/docker.sock"));
+
+    let tmpfs = volume("{type: tmpfs, target: /tmp}");
+    assert_eq!(tmpfs.volume_name(), None);
+    assert_eq!(tmpfs.host_path(), None);
+}
+
+#[test]
+fn test_secrets_and_configs_parsing() {
+    let references = ResourceReference::parse_sequence(
+        &serde_yaml::from_str("[db_password, {source: api_key, target: api.key}, {source: cert, target: /etc/ssl/cert.pem}]")
+            .unwrap(),
+    )
+    .unwrap();
+    let targets: Vec<String> = references
+        .iter()
+        .map(|reference| reference.target_path("secret"))
+        .collect();
+    assert_eq!(
+        targets,
+        vec![
+            "/run/secrets/db_password",
+            "/run/secrets/api.key",
+            "/etc/ssl/cert.pem"
+        ]
+    );
+    assert_eq!(references[0].target_path("config"), "/db_password");
+
+    let resources = parse_top_level_resources(
+        &serde_yaml::from_str(
+            "{db_password: {file: ./db_password.txt}, api_key: {environment: API_KEY}, cert: {external: true}, other: {}}",
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        resources.get("db_password"),
+        Some(&Some("file: ./db_password.txt".to_string()))
+    );
+    assert_eq!(
+        resources.get("api_key"),
+        Some(&Some("env: API_KEY".to_string()))
+    );
+    assert_eq!(resources.get("cert"), Some(&Some("external".to_string())));
+    assert_eq!(resources.get("other"), Some(&None));
+}
+
+#[test]
+fn test_dependency_edges() {
+    let container = test_container(
+        "container_1",
+        r#"
+image: app
+depends_on: [db]
+links: ["cache", "auth:sso"]
+volumes_from: ["storage:ro", "service:logs", "container:external"]
+network_mode: "service:vpn"
+ipc: "service:shm"
+pid: host
+"#,
+    );
+    let edges: Vec<(String, EdgeKind, String)> = container
+        .dependency_edges()
+        .into_iter()
+        .map(|edge| (edge.name.clone(), edge.kind, edge.label()))
+        .collect();
+    let edge =
+        |name: &str, kind: EdgeKind, label: &str| (name.to_string(), kind, label.to_string());
+    assert_eq!(
+        edges,
+        vec![
+            edge("db", EdgeKind::DependsOn, "depends_on"),
+            edge("cache", EdgeKind::Links, "links"),
+            edge("auth", EdgeKind::Links, "links: sso"),
+            edge("storage", EdgeKind::VolumesFrom, "volumes_from: ro"),
+            edge("logs", EdgeKind::VolumesFrom, "volumes_from"),
+            edge("vpn", EdgeKind::NetworkMode, "network_mode"),
+            edge("shm", EdgeKind::Ipc, "ipc"),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_connections() {
+    let container = test_container(
+        "container_1",
+        "{depends_on: [db, cache], links: [\"db:database\"], volumes_from: [db]}",
+    );
+    let edges = container.dependency_edges();
+    let connections = edges
+        .iter()
+        .map(|edge| ("app", "container_1", edge))
+        .collect();
+    let merged: Vec<(String, String)> = merge_connections(connections)
+        .into_iter()
+        .map(|(_, _, edges)| {
+            let labels = edges.iter().map(|edge| edge.label()).collect::<Vec<_>>();
+            (edges[0].name.clone(), labels.join(", "))
+        })
+        .collect();
+    let edge = |name: &str, labels: &str| (name.to_string(), labels.to_string());
+    assert_eq!(
+        merged,
+        vec![
+            edge("db", "depends_on, links: database, volumes_from"),
+            edge("cache", "depends_on"),
+        ]
+    );
+}
+
+#[test]
+fn test_depends_on_conditions() {
+    let container = test_container(
+        "container_1",
+        r#"
+image: app
+depends_on:
+  db:
+    condition: service_healthy
+    restart: true
+  migration:
+    condition: service_completed_successfully
+  cache:
+    condition: service_started
+    required: false
+healthcheck:
+  test: ["CMD", "curl", "-f", "http://localhost"]
+"#,
+    );
+    assert!(container.healthcheck);
+    let edges: Vec<(String, Option<String>, bool)> = container
+        .dependency_edges()
+        .into_iter()
+        .map(|edge| (edge.name, edge.detail, edge.required))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (
+                "db".to_string(),
+                Some("service_healthy, restart".to_string()),
+                true
+            ),
+            (
+                "migration".to_string(),
+                Some("service_completed_successfully".to_string()),
+                true
+            ),
+            ("cache".to_string(), Some("optional".to_string()), false),
+        ]
+    );
+    assert!(!DockerContainer::parse_healthcheck(
+        &serde_yaml::from_str("test: [\"NONE\"]").unwrap()
+    ));
+    assert!(!DockerContainer::parse_healthcheck(
+        &serde_yaml::from_str("disable: true").unwrap()
+    ));
+}
+
+#[test]
+fn test_deploy_parsing() {
+    let container = test_container(
+        "container_1",
+        r#"
+image: app
+deploy:
+  mode: replicated
+  replicas: 3
+  resources:
+    limits:
+      cpus: '0.50'
+      memory: 50M
+    reservations:
+      memory: 20M
+  placement:
+    constraints:
+      - node.role == manager
+"#,
+    );
+    assert_eq!(
+        container.placement_lines(),
+        vec!["placement: node.role == manager".to_string()]
+    );
+    assert_eq!(Some("×3".to_string()), container.replicas_badge());
+    assert_eq!(
+        Some("limits: 0.50 cpus, 50M | reservations: 20M".to_string()),
+        container.resources_line()
+    );
+
+    let scaled = test_container("container_2", "scale: 2");
+    assert_eq!(Some("×2".to_string()), scaled.replicas_badge());
+    assert_eq!(None, scaled.resources_line());
+    assert!(scaled.placement_lines().is_empty());
+
+    let global = test_container("container_3", "deploy: { mode: global }");
+    assert_eq!(Some("global".to_string()), global.replicas_badge());
+}
+
+#[test]
+fn test_network_members() {
+    let container = |name: &str, networks: &[&str]| {
+        let mut container = DockerContainer::new(name.to_string());
+        container.networks = Some(networks.iter().map(|n| n.to_string()).collect());
+        container
+    };
+    let containers = HashMap::from([
+        ("proxy", container("proxy", &["front"])),
+        ("db", container("db", &["back"])),
+        ("app", container("app", &["front", "back"])),
+        ("web", container("web", &["front"])),
+        ("tool", container("tool", &[])),
+    ]);
+    let networks = vec![
+        DockerNetwork::new("front", NetworkKind::Declared),
+        DockerNetwork::new("back", NetworkKind::Declared),
+    ];
+    let mut order: Vec<String> = ["db", "tool", "proxy", "app", "web"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let members = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    assert_eq!(
+        find_network_members(&networks, &order, &containers),
+        vec![
+            (networks[0].clone(), members(&["proxy", "app", "web"])),
+            (networks[1].clone(), members(&["db", "app"])),
+        ]
+    );
+
+    order_by_network_membership(&mut order, &networks, &containers);
+    assert_eq!(order, vec!["proxy", "web", "app", "db", "tool"]);
+    assert_eq!(
+        find_network_members(&networks, &order, &containers),
+        vec![
+            (networks[0].clone(), members(&["proxy", "web", "app"])),
+            (networks[1].clone(), members(&["app", "db"])),
+        ]
+    );
+
+    // the bridged networks follow each other regardless of the declaration order
+    let containers = HashMap::from([
+        ("s1", container("s1", &["a"])),
+        ("s2", container("s2", &["a", "c"])),
+        ("s3", container("s3", &["c", "b"])),
+        ("s4", container("s4", &["b"])),
+    ]);
+    let networks = ["a", "b", "c"]
+        .iter()
+        .map(|name| DockerNetwork::new(name, NetworkKind::Declared))
+        .collect::<Vec<_>>();
+    let mut order: Vec<String> = members(&["s4", "s3", "s2", "s1"]);
+    order_by_network_membership(&mut order, &networks, &containers);
+    assert_eq!(order, vec!["s1", "s2", "s3", "s4"]);
+}
+
+#[test]
+fn test_collect_networks() {
+    let container = |yaml: &str| test_container("container", yaml);
+    let containers = [
+        container("image: app"),
+        container("network_mode: host"),
+        container("network_mode: none"),
+        container("network_mode: \"service:app\""),
+        container("networks: [shared]"),
+    ];
+    assert!(containers[3].network_memberships().is_empty());
+    let declared = parse_top_level_networks(
+        &serde_yaml::from_str(
+            r#"
+shared:
+  external: true
+legacy:
+  external:
+    name: legacy_network
+"#,
+        )
+        .unwrap(),
+    );
+    let networks = collect_networks(declared.clone(), containers.iter(), "demo", true);
+    let labels: Vec<String> = networks.iter().map(DockerNetwork::label).collect();
+    assert_eq!(
+        labels,
+        vec![
+            "shared (external)",
+            "legacy (external)",
+            "demo_default",
+            "host",
+            "none"
+        ]
+    );
+    let networks = collect_networks(declared, containers.iter(), "demo", false);
+    assert_eq!(4, networks.len());
+
+    // networks without members are not rendered
+    let order = vec!["app".to_string()];
+    let no_members = find_network_members(
+        &[DockerNetwork::new("empty", NetworkKind::Declared)],
+        &order,
+        &HashMap::from([("app", containers[0].clone())]),
+    );
+    assert!(no_members.is_empty());
+}
+
+#[test]
+fn test_network_details_parsing() {
+    let networks = parse_top_level_networks(
+        &serde_yaml::from_str(
+            r#"
+backend:
+  driver: bridge
+  internal: true
+  ipam:
+    config:
+      - subnet: 172.16.238.0/24
+        gateway: 172.16.238.1
+      - subnet: "2001:3984:3989::/64"
+frontend:
+  driver: custom-driver-1
+"#,
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        networks[0].details(),
+        vec![
+            "driver: bridge, internal",
+            "subnet: 172.16.238.0/24, gateway: 172.16.238.1",
+            "subnet: 2001:3984:3989::/64"
+        ]
+    );
+    assert_eq!(networks[1].details(), vec!["driver: custom-driver-1"]);
+
+    let container = test_container(
+        "container_1",
+        r#"
+image: postgres
+networks:
+  backend:
+    ipv4_address: 172.16.238.10
+    ipv6_address: "2001:3984:3989::10"
+    aliases:
+      - db
+  frontend:
+    aliases: [database]
+  monitoring:
+"#,
+    );
+    assert_eq!(
+        container.networks,
+        Some(vec![
+            "backend".to_string(),
+            "frontend".to_string(),
+            "monitoring".to_string()
+        ])
+    );
+    assert_eq!(
+        container.network_address_lines(),
+        vec![
+            "backend: 172.16.238.10, 2001:3984:3989::10 (aliases: db)",
+            "frontend (aliases: database)"
+        ]
+    );
+}
+
+#[test]
+fn test_group_services() {
+    let containers = HashMap::from([
+        (
+            "api-gateway",
+            test_container("api-gateway", "labels: [tier=edge]"),
+        ),
+        (
+            "api-users",
+            test_container("api-users", "labels: {tier: core}"),
+        ),
+        ("db", test_container("db", "networks: [backend]")),
+    ]);
+    let order: Vec<String> = ["api-gateway", "api-users", "db"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let alignment = |group_by: &str| Alignment {
+        mode: "grouped".to_string(),
+        direction: "top-down".to_string(),
+        seed: 42,
+        iterations: 300,
+        force_networks: true,
+        force_volumes: true,
+        columns: 4,
+        row_gap: 60,
+        column_gap: 60,
+        sort_by: "traversal".to_string(),
+        group_by: group_by.to_string(),
+        group_label: "tier".to_string(),
+        group_separator: "-".to_string(),
+    };
+    let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    assert_eq!(
+        group_services(&order, &containers, &alignment("prefix")),
+        vec![
+            (
+                Some("api".to_string()),
+                names(&["api-gateway", "api-users"])
+            ),
+            (Some("db".to_string()), names(&["db"])),
+        ]
+    );
+    assert_eq!(
+        group_services(&order, &containers, &alignment("label")),
+        vec![
+            (Some("edge".to_string()), names(&["api-gateway"])),
+            (Some("core".to_string()), names(&["api-users"])),
+            (None, names(&["db"])),
+        ]
+    );
+    assert_eq!(
+        group_services(&order, &containers, &alignment("network")),
+        vec![
+            (None, names(&["api-gateway", "api-users"])),
+            (None, names(&["db"])),
+        ]
+    );
+}
+
+#[test]
+fn test_sort_services() {
+    let container = |yaml: &str| test_container("container", yaml);
+    let containers = HashMap::from([
+        ("web", container("networks: [front]")),
+        ("db", container("networks: [back]")),
+        ("tool", container("network_mode: none")),
+        ("api", container("networks: [front, back]")),
+        ("host", container("network_mode: \"service:web\"")),
+    ]);
+    let order: Vec<String> = ["db", "api", "web", "host", "tool"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(
+        sort_services(&order, &containers, "traversal"),
+        vec!["db", "api", "web", "host", "tool"]
+    );
+    assert_eq!(
+        sort_services(&order, &containers, "name"),
+        vec!["api", "db", "host", "tool", "web"]
+    );
+    assert_eq!(
+        sort_services(&order, &containers, "network"),
+        vec!["db", "api", "web", "tool", "host"]
+    );
 }
@@ -0,0 +1,714 @@
+//! Orthogonal routing of the connections between the services.
+//! The routes go around the placed services (and their ports) instead of through them:
+//! a shortest path with as few turns as possible is searched on the grid made of the lines
+//! running along the obstacles.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Cost of every turn of a route, keeps the routes simple
+const BEND_PENALTY: i64 = 40;
+/// Cost of running along a segment already taken by another route, spreads parallel routes apart
+const SHARED_SEGMENT_PENALTY: i64 = 60;
+/// States the search may expand while spreading a route apart from the others, past it the route
+/// is searched again ignoring the other routes (the crowded routes would explore the whole grid)
+const MAX_EXPANDED_STATES: usize = 20_000;
+
+/// Rectangle the routes should not cross
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Obstacle {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn center(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Whether the horizontal or vertical segment crosses the obstacle grown by `margin`
+    #[cfg(test)]
+    fn crosses(&self, from: (i32, i32), to: (i32, i32), margin: i32) -> bool {
+        let (x0, x1) = (self.x - margin, self.x + self.width + margin);
+        let (y0, y1) = (self.y - margin, self.y + self.height + margin);
+        let (min_x, max_x) = (from.0.min(to.0), from.0.max(to.0));
+        let (min_y, max_y) = (from.1.min(to.1), from.1.max(to.1));
+        if min_y == max_y {
+            y0 < min_y && min_y < y1 && min_x < x1 && max_x > x0
+        } else {
+            x0 < min_x && min_x < x1 && min_y < y1 && max_y > y0
+        }
+    }
+}
+
+/// Side of a service a route is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Direction pointing away from the service
+    fn normal(self) -> (i32, i32) {
+        match self {
+            Side::Top => (0, -1),
+            Side::Bottom => (0, 1),
+            Side::Left => (-1, 0),
+            Side::Right => (1, 0),
+        }
+    }
+}
+
+/// Sides of the two services facing each other.
+/// The services are connected vertically when the vertical gap between them is the largest one.
+pub fn facing_sides(source: &Obstacle, target: &Obstacle) -> (Side, Side) {
+    let gap_x = (target.x - (source.x + source.width)).max(source.x - (target.x + target.width));
+    let gap_y = (target.y - (source.y + source.height)).max(source.y - (target.y + target.height));
+    let ((source_x, source_y), (target_x, target_y)) = (source.center(), target.center());
+    if gap_y >= gap_x {
+        if target_y < source_y {
+            (Side::Top, Side::Bottom)
+        } else {
+            (Side::Bottom, Side::Top)
+        }
+    } else if target_x < source_x {
+        (Side::Left, Side::Right)
+    } else {
+        (Side::Right, Side::Left)
+    }
+}
+
+/// Point where a route is attached to a service
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Anchor {
+    x: i32,
+    y: i32,
+    side: Side,
+}
+
+impl Anchor {
+    /// The `index`-th of `count` anchors spread evenly along the side
+    fn new(obstacle: &Obstacle, side: Side, index: usize, count: usize) -> Self {
+        let (index, count) = (index as i32 + 1, count as i32 + 1);
+        let (x, y) = match side {
+            Side::Top => (obstacle.x + obstacle.width * index / count, obstacle.y),
+            Side::Bottom => (
+                obstacle.x + obstacle.width * index / count,
+                obstacle.y + obstacle.height,
+            ),
+            Side::Left => (obstacle.x, obstacle.y + obstacle.height * index / count),
+            Side::Right => (
+                obstacle.x + obstacle.width,
+                obstacle.y + obstacle.height * index / count,
+            ),
+        };
+        Self { x, y, side }
+    }
+
+    /// Point at `distance` from the anchor in the direction away from the service
+    fn stub(&self, distance: i32) -> (i32, i32) {
+        let (dx, dy) = self.side.normal();
+        (self.x + dx * distance, self.y + dy * distance)
+    }
+}
+
+/// Horizontal or vertical segment between two neighbouring grid points
+type Segment = ((i32, i32), (i32, i32));
+
+/// Route end attached to a side: position of the other service along the side,
+/// connection index and whether it's the source end
+type Attachment = (i32, usize, bool);
+
+/// Above this number of grid points the routes are simple elbows, the search would take too long
+const MAX_GRID_POINTS: usize = 2_000_000;
+
+pub struct Router {
+    obstacles: Vec<Obstacle>,
+    /// Rectangles the routes may cross but not run along (network boundaries)
+    boundaries: Vec<Obstacle>,
+    /// Distance kept between the routes and the obstacles
+    clearance: i32,
+    /// How many routes run along a grid segment
+    used_segments: HashMap<Segment, i64>,
+}
+
+impl Router {
+    pub fn new(obstacles: Vec<Obstacle>, boundaries: Vec<Obstacle>, clearance: i32) -> Self {
+        Self {
+            obstacles,
+            boundaries,
+            clearance: clearance.max(2),
+            used_segments: HashMap::new(),
+        }
+    }
+
+    /// Routes the connections given as pairs of obstacle indices (source, target).
+    /// Each route is attached to the sides of the services facing each other, several routes
+    /// attached to the same side are spread along it. The routes are returned as absolute points.
+    pub fn route_all(&mut self, connections: &[(usize, usize)]) -> Vec<Vec<[i32; 2]>> {
+        let sides = connections
+            .iter()
+            .map(|&(source, target)| facing_sides(&self.obstacles[source], &self.obstacles[target]))
+            .collect::<Vec<_>>();
+        let mut attachments: HashMap<(usize, Side), Vec<Attachment>> = HashMap::new();
+        for (i, (&(source, target), &(source_side, target_side))) in
+            connections.iter().zip(sides.iter()).enumerate()
+        {
+            let along = |obstacle: &Obstacle, side: Side| {
+                let (x, y) = obstacle.center();
+                if matches!(side, Side::Top | Side::Bottom) {
+                    x
+                } else {
+                    y
+                }
+            };
+            attachments.entry((source, source_side)).or_default().push((
+                along(&self.obstacles[target], source_side),
+                i,
+                true,
+            ));
+            attachments.entry((target, target_side)).or_default().push((
+                along(&self.obstacles[source], target_side),
+                i,
+                false,
+            ));
+        }
+        let mut anchors = vec![(None, None); connections.len()];
+        for ((obstacle, side), mut ends) in attachments {
+            // the routes are ordered the same way as the services they lead to, so they don't cross
+            ends.sort();
+            let count = ends.len();
+            for (index, (_, i, is_source)) in ends.into_iter().enumerate() {
+                let anchor = Anchor::new(&self.obstacles[obstacle], side, index, count);
+                if is_source {
+                    anchors[i].0 = Some(anchor);
+                } else {
+                    anchors[i].1 = Some(anchor);
+                }
+            }
+        }
+        let anchors = anchors
+            .into_iter()
+            .map(|(from, to)| (from.unwrap(), to.unwrap()))
+            .collect::<Vec<_>>();
+        // the grid is shared by all the routes, so the blocked segments are found only once
+        let grid = Grid::new(
+            &self.obstacles,
+            &self.boundaries,
+            self.clearance,
+            anchors
+                .iter()
+                .flat_map(|(from, to)| [from.stub(self.clearance), to.stub(self.clearance)]),
+        );
+        let mut search = grid.as_ref().map(Search::new);
+        anchors
+            .into_iter()
+            .map(|(from, to)| self.route(grid.as_ref().zip(search.as_mut()), from, to))
+            .collect()
+    }
+
+    fn route(
+        &mut self,
+        grid: Option<(&Grid, &mut Search)>,
+        from: Anchor,
+        to: Anchor,
+    ) -> Vec<[i32; 2]> {
+        let start = from.stub(self.clearance);
+        let end = to.stub(self.clearance);
+        let path = grid
+            .and_then(|(grid, search)| {
+                let mut shortest_path = |spread| {
+                    let path =
+                        self.shortest_path(grid, search, start, from.side, end, to.side, spread);
+                    search.reset();
+                    path
+                };
+                shortest_path(true).or_else(|| shortest_path(false))
+            })
+            .unwrap_or_else(|| vec![start, (end.0, start.1), end]);
+        for segment in path.windows(2) {
+            *self
+                .used_segments
+                .entry(ordered(segment[0], segment[1]))
+                .or_default() += 1;
+        }
+        let mut points = vec![[from.x, from.y]];
+        points.extend(path.into_iter().map(|(x, y)| [x, y]));
+        points.push([to.x, to.y]);
+        simplify(points)
+    }
+
+    /// A* search over the grid made of the lines running along the obstacles (at `clearance`)
+    /// and between them. The state includes the direction, so turns can be penalized.
+    /// When `spread` the segments taken by the other routes are penalized, the search gives up
+    /// after `MAX_EXPANDED_STATES` then.
+    #[allow(clippy::too_many_arguments)]
+    fn shortest_path(
+        &self,
+        grid: &Grid,
+        search: &mut Search,
+        start: (i32, i32),
+        start_side: Side,
+        end: (i32, i32),
+        end_side: Side,
+        spread: bool,
+    ) -> Option<Vec<(i32, i32)>> {
+        let (xs, ys) = (&grid.xs, &grid.ys);
+        let index_of = |lines: &[i32], value: i32| lines.binary_search(&value).ok();
+        let start_node = (index_of(xs, start.0)?, index_of(ys, start.1)?);
+        let end_node = (index_of(xs, end.0)?, index_of(ys, end.1)?);
+        let directions = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+        let direction_index = |side: Side| directions.iter().position(|d| *d == side.normal());
+        let start_direction = direction_index(start_side)?;
+        // the route enters the target moving against the normal of its side
+        let (end_dx, end_dy) = end_side.normal();
+        let end_direction = directions.iter().position(|d| *d == (-end_dx, -end_dy))?;
+
+        let point = |(i, j): (usize, usize)| (xs[i], ys[j]);
+        // the turns left are estimated too, otherwise every state closer than the bends of the
+        // route would be expanded. Without spreading the estimate is weighted, so the search heads
+        // to the end instead of proving the route is the shortest one.
+        let weight = if spread { 1 } else { 2 };
+        let heuristic = |(x, y): (i32, i32), d: usize| {
+            let offset = (end.0 - x, end.1 - y);
+            let bends = min_bends(directions[d], directions[end_direction], offset);
+            weight * ((offset.0.abs() + offset.1.abs()) as i64 + bends * BEND_PENALTY)
+        };
+        let state = |(i, j): (usize, usize), d: usize| (i * ys.len() + j) * 4 + d;
+
+        // among the equally promising states the ones closer to the end are taken first
+        let mut queue = BinaryHeap::new();
+        let first = state(start_node, start_direction);
+        search.update(first, 0, first);
+        queue.push(Reverse((heuristic(start, start_direction), 0, first)));
+        let mut best: Option<(i64, usize)> = None;
+        let mut expanded = 0;
+        while let Some(Reverse((estimate, negated_cost, current))) = queue.pop() {
+            if best.is_some_and(|(cost, _)| estimate >= cost) {
+                break;
+            }
+            let cost = search.costs[current];
+            // the state has been reached more cheaply since it was queued
+            if -negated_cost > cost {
+                continue;
+            }
+            expanded += 1;
+            if spread && expanded > MAX_EXPANDED_STATES {
+                return None;
+            }
+            let direction = current % 4;
+            let node = ((current / 4) / ys.len(), (current / 4) % ys.len());
+            if node == end_node {
+                let total = cost
+                    + if direction == end_direction {
+                        0
+                    } else {
+                        BEND_PENALTY
+                    };
+                if best.is_none_or(|(best_cost, _)| total < best_cost) {
+                    best = Some((total, current));
+                }
+                continue;
+            }
+            for (next_direction, (dx, dy)) in directions.iter().enumerate() {
+                // going back is never useful
+                if directions[direction] == (-dx, -dy) {
+                    continue;
+                }
+                let (Some(i), Some(j)) = (
+                    node.0.checked_add_signed(*dx as isize),
+                    node.1.checked_add_signed(*dy as isize),
+                ) else {
+                    continue;
+                };
+                if i >= xs.len()
+                    || j >= ys.len()
+                    || grid.is_blocked(node, (i, j))
+                    || ((i, j) != end_node && grid.is_blocked_node((i, j)))
+                {
+                    continue;
+                }
+                let (from, to) = (point(node), point((i, j)));
+                let length = ((to.0 - from.0).abs() + (to.1 - from.1).abs()) as i64;
+                let shared = if spread {
+                    self.used_segments
+                        .get(&ordered(from, to))
+                        .copied()
+                        .unwrap_or_default()
+                } else {
+                    0
+                };
+                let next_cost = cost
+                    + length
+                    + shared * SHARED_SEGMENT_PENALTY
+                    + if next_direction == direction {
+                        0
+                    } else {
+                        BEND_PENALTY
+                    };
+                let next = state((i, j), next_direction);
+                if next_cost < search.costs[next] {
+                    search.update(next, next_cost, current);
+                    queue.push(Reverse((
+                        next_cost + heuristic(to, next_direction),
+                        -next_cost,
+                        next,
+                    )));
+                }
+            }
+        }
+
+        let (_, mut current) = best?;
+        let mut path = vec![point(((current / 4) / ys.len(), (current / 4) % ys.len()))];
+        while current != first {
+            current = search.previous[current];
+            path.push(point(((current / 4) / ys.len(), (current / 4) % ys.len())));
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Grid made of the lines running along the obstacles and the boundaries (at `clearance`) and
+/// between them, along with the grid points and segments blocked by the obstacles
+struct Grid {
+    xs: Vec<i32>,
+    ys: Vec<i32>,
+    /// Points inside the obstacles, indexed by `i * ys.len() + j`
+    blocked_nodes: Vec<bool>,
+    /// Segments from the point `(i, j)` to `(i + 1, j)` crossing the obstacles or running along the boundaries
+    blocked_horizontal: Vec<bool>,
+    /// Segments from the point `(i, j)` to `(i, j + 1)` crossing the obstacles or running along the boundaries
+    blocked_vertical: Vec<bool>,
+}
+
+impl Grid {
+    /// The obstacles are grown by the half of the `clearance`, the `points` (route ends) are on the grid.
+    /// None when the grid has more than `MAX_GRID_POINTS`.
+    fn new(
+        obstacles: &[Obstacle],
+        boundaries: &[Obstacle],
+        clearance: i32,
+        points: impl Iterator<Item = (i32, i32)> + Clone,
+    ) -> Option<Self> {
+        // the routes may run on both sides of the boundaries
+        let xs = grid_lines(
+            obstacles
+                .iter()
+                .flat_map(|o| [o.x - clearance, o.x + o.width + clearance])
+                .chain(boundaries.iter().flat_map(|b| {
+                    [
+                        b.x - clearance,
+                        b.x + clearance,
+                        b.x + b.width - clearance,
+                        b.x + b.width + clearance,
+                    ]
+                }))
+                .chain(points.clone().map(|(x, _)| x)),
+        );
+        let ys = grid_lines(
+            obstacles
+                .iter()
+                .flat_map(|o| [o.y - clearance, o.y + o.height + clearance])
+                .chain(boundaries.iter().flat_map(|b| {
+                    [
+                        b.y - clearance,
+                        b.y + clearance,
+                        b.y + b.height - clearance,
+                        b.y + b.height + clearance,
+                    ]
+                }))
+                .chain(points.map(|(_, y)| y)),
+        );
+        let size = xs.len() * ys.len();
+        if size > MAX_GRID_POINTS {
+            return None;
+        }
+        let mut grid = Self {
+            blocked_nodes: vec![false; size],
+            blocked_horizontal: vec![false; size],
+            blocked_vertical: vec![false; size],
+            xs,
+            ys,
+        };
+        let margin = clearance / 2;
+        for o in obstacles {
+            let (x0, x1) = (o.x - margin, o.x + o.width + margin);
+            let (y0, y1) = (o.y - margin, o.y + o.height + margin);
+            // lines strictly inside the grown obstacle
+            let inner_xs =
+                grid.xs.partition_point(|x| *x <= x0)..grid.xs.partition_point(|x| *x < x1);
+            let inner_ys =
+                grid.ys.partition_point(|y| *y <= y0)..grid.ys.partition_point(|y| *y < y1);
+            // segments starting at these lines overlap the grown obstacle
+            let crossing_xs = inner_xs.start.saturating_sub(1)..inner_xs.end;
+            let crossing_ys = inner_ys.start.saturating_sub(1)..inner_ys.end;
+            let ny = grid.ys.len();
+            for i in inner_xs.clone() {
+                for j in inner_ys.clone() {
+                    grid.blocked_nodes[i * ny + j] = true;
+                }
+                for j in crossing_ys.clone() {
+                    grid.blocked_vertical[i * ny + j] = true;
+                }
+            }
+            for i in crossing_xs {
+                for j in inner_ys.clone() {
+                    grid.blocked_horizontal[i * ny + j] = true;
+                }
+            }
+        }
+        // the lines closer than `margin` to the sides of the boundaries are blocked along them
+        let within = |lines: &[i32], value: i32| {
+            lines.partition_point(|line| *line <= value - margin)
+                ..lines.partition_point(|line| *line < value + margin)
+        };
+        let along = |lines: &[i32], from: i32, to: i32| {
+            lines
+                .partition_point(|line| *line <= from)
+                .saturating_sub(1)..lines.partition_point(|line| *line < to)
+        };
+        for b in boundaries {
+            let ny = grid.ys.len();
+            for side_x in [b.x, b.x + b.width] {
+                for i in within(&grid.xs, side_x) {
+                    for j in along(&grid.ys, b.y, b.y + b.height) {
+                        grid.blocked_vertical[i * ny + j] = true;
+                    }
+                }
+            }
+            for side_y in [b.y, b.y + b.height] {
+                for j in within(&grid.ys, side_y) {
+                    for i in along(&grid.xs, b.x, b.x + b.width) {
+                        grid.blocked_horizontal[i * ny + j] = true;
+                    }
+                }
+            }
+        }
+        Some(grid)
+    }
+
+    fn is_blocked_node(&self, (i, j): (usize, usize)) -> bool {
+        self.blocked_nodes[i * self.ys.len() + j]
+    }
+
+    /// Whether the segment between the neighbouring points crosses an obstacle
+    fn is_blocked(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let ((i, j), _) = if a <= b { (a, b) } else { (b, a) };
+        if a.1 == b.1 {
+            self.blocked_horizontal[i * self.ys.len() + j]
+        } else {
+            self.blocked_vertical[i * self.ys.len() + j]
+        }
+    }
+}
+
+/// Costs of the states of the A* search, kept between the routes to avoid allocating them
+/// for every route. Only the reached states are reset after a search.
+struct Search {
+    costs: Vec<i64>,
+    previous: Vec<usize>,
+    reached: Vec<usize>,
+}
+
+impl Search {
+    fn new(grid: &Grid) -> Self {
+        let size = grid.xs.len() * grid.ys.len() * 4;
+        Self {
+            costs: vec![i64::MAX; size],
+            previous: vec![0; size],
+            reached: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, state: usize, cost: i64, previous: usize) {
+        if self.costs[state] == i64::MAX {
+            self.reached.push(state);
+        }
+        self.costs[state] = cost;
+        self.previous[state] = previous;
+    }
+
+    fn reset(&mut self) {
+        for state in self.reached.drain(..) {
+            self.costs[state] = i64::MAX;
+        }
+    }
+}
+
+/// Sorted unique coordinates along with the middles between the neighbouring ones,
+/// so the routes can also run in the middle of the channels between the obstacles.
+fn grid_lines(coordinates: impl Iterator<Item = i32>) -> Vec<i32> {
+    let mut lines = coordinates.collect::<Vec<_>>();
+    lines.sort();
+    lines.dedup();
+    let middles = lines
+        .windows(2)
+        .map(|pair| (pair[0] + pair[1]) / 2)
+        .collect::<Vec<_>>();
+    lines.extend(middles);
+    lines.sort();
+    lines.dedup();
+    lines
+}
+
+/// Least number of turns (arriving in another direction counts as one) a route moving in
+/// `direction` takes to reach the point at `offset` moving in `end_direction`
+fn min_bends(direction: (i32, i32), end_direction: (i32, i32), offset: (i32, i32)) -> i64 {
+    let dot =
+        |(ax, ay): (i32, i32), (bx, by): (i32, i32)| ax as i64 * bx as i64 + ay as i64 * by as i64;
+    let straight_ahead = offset.0 as i64 * direction.1 as i64
+        == offset.1 as i64 * direction.0 as i64
+        && dot(offset, direction) >= 0;
+    if straight_ahead {
+        (direction != end_direction).into()
+    } else if dot(direction, end_direction) == 0
+        && dot(offset, direction) >= 0
+        && dot(offset, end_direction) >= 0
+    {
+        1
+    } else {
+        2
+    }
+}
+
+fn ordered(a: (i32, i32), b: (i32, i32)) -> Segment {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Drops the duplicated points and the points in the middle of straight lines
+fn simplify(points: Vec<[i32; 2]>) -> Vec<[i32; 2]> {
+    let mut simplified: Vec<[i32; 2]> = Vec::with_capacity(points.len());
+    for point in points {
+        if simplified.last() == Some(&point) {
+            continue;
+        }
+        if let [.., a, b] = simplified[..] {
+            if (a[0] == b[0] && b[0] == point[0]) || (a[1] == b[1] && b[1] == point[1]) {
+                simplified.pop();
+            }
+        }
+        simplified.push(point);
+    }
+    simplified
+}
+
+#[test]
+fn test_route_around_obstacle() {
+    // a service sits right between the two connected ones
+    let obstacles = vec![
+        Obstacle::new(0, 0, 140, 60),
+        Obstacle::new(0, 400, 140, 60),
+        Obstacle::new(-20, 200, 180, 60),
+    ];
+    let mut router = Router::new(obstacles.clone(), vec![], 20);
+    let routes = router.route_all(&[(0, 1), (0, 1)]);
+    for route in &routes {
+        assert_eq!(route.first().unwrap()[1], 60); // leaves the bottom side
+        assert_eq!(route.last().unwrap()[1], 400); // enters the top side
+        for segment in route.windows(2) {
+            let (from, to) = (
+                (segment[0][0], segment[0][1]),
+                (segment[1][0], segment[1][1]),
+            );
+            assert!(from.0 == to.0 || from.1 == to.1, "the route is orthogonal");
+            assert!(
+                !obstacles[2].crosses(from, to, 0),
+                "the route avoids the obstacle"
+            );
+        }
+    }
+    // parallel connections are attached at different points
+    assert_ne!(routes[0].first(), routes[1].first());
+    assert_ne!(routes[0].last(), routes[1].last());
+}
+
+#[test]
+fn test_route_across_boundary() {
+    // the first service is inside the boundary, the second one right below its bottom side
+    let obstacles = vec![
+        Obstacle::new(0, 0, 140, 60),
+        Obstacle::new(300, 100, 140, 60),
+    ];
+    let boundary = Obstacle::new(-20, -40, 180, 120);
+    let routes = Router::new(obstacles, vec![boundary], 20).route_all(&[(0, 1)]);
+    for segment in routes[0].windows(2) {
+        let ([x0, y0], [x1, _]) = (segment[0], segment[1]);
+        if x0 == x1 {
+            assert!(
+                (x0 - boundary.x).abs() >= 10 && (x0 - boundary.x - boundary.width).abs() >= 10
+            );
+        } else {
+            assert!(
+                (y0 - boundary.y).abs() >= 10 && (y0 - boundary.y - boundary.height).abs() >= 10
+            );
+        }
+    }
+}
+
+#[test]
+fn test_facing_sides() {
+    let service = Obstacle::new(0, 0, 140, 60);
+    assert_eq!(
+        (Side::Bottom, Side::Top),
+        facing_sides(&service, &Obstacle::new(40, 300, 140, 60))
+    );
+    assert_eq!(
+        (Side::Left, Side::Right),
+        facing_sides(&service, &Obstacle::new(-400, 20, 140, 60))
+    );
+    assert_eq!(0, min_bends((1, 0), (1, 0), (50, 0)));
+    assert_eq!(1, min_bends((1, 0), (0, 1), (50, 30)));
+    assert_eq!(2, min_bends((1, 0), (0, 1), (-50, 30)));
+    assert_eq!(2, min_bends((1, 0), (1, 0), (50, 30)));
+    assert_eq!(
+        vec![[0, 0], [0, 20], [30, 20]],
+        simplify(vec![[0, 0], [0, 10], [0, 20], [0, 20], [30, 20]])
+    );
+}
+
+#[test]
+fn test_route_many_services() {
+    // services placed in steps, each one depends on the first and the previous one
+    let count = 120;
+    let obstacles = (0..count)
+        .map(|i| Obstacle::new(i * 200, i * 60, 140, 60))
+        .collect::<Vec<_>>();
+    let connections = (1..count as usize)
+        .flat_map(|i| [(i, 0), (i, i - 1)])
+        .collect::<Vec<_>>();
+    let started = std::time::Instant::now();
+    let routes = Router::new(obstacles.clone(), vec![], 20).route_all(&connections);
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(60),
+        "routing took {:?}",
+        started.elapsed()
+    );
+    assert_eq!(connections.len(), routes.len());
+    for route in &routes {
+        for segment in route.windows(2) {
+            let (from, to) = (
+                (segment[0][0], segment[0][1]),
+                (segment[1][0], segment[1][1]),
+            );
+            assert!(obstacles.iter().all(|o| !o.crosses(from, to, 0)));
+        }
+    }
+}